@@ -10,7 +10,14 @@ mod inner {
 
         for &(src, dst) in &[
             ("src/config.in.rs", "config.rs"),
+            ("src/counter.in.rs", "counter.rs"),
+            ("src/error_log.in.rs", "error_log.rs"),
+            ("src/welcome.in.rs", "welcome.rs"),
+            ("src/command/convert.in.rs", "convert.rs"),
+            ("src/command/remind.in.rs", "remind.rs"),
             ("src/command/tag.in.rs", "tag.rs"),
+            ("src/command/urban.in.rs", "urban.rs"),
+            ("src/command/weather.in.rs", "weather.rs"),
             ("src/command/xkcd.in.rs", "xkcd.rs"),
         ] {
             let src = Path::new(src);
@@ -0,0 +1,77 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Stores the channels to which guilds have chosen to confine the bot's
+//! commands. A guild with no such channels allows commands in every channel.
+
+use error::Result;
+use serenity::model::{ChannelId, GuildId};
+use std::collections::{HashMap, HashSet};
+use util::{JsonStore, data_path};
+
+const BOT_CHANNELS_FILE: &'static str = "bot_channels.json";
+
+lazy_static! {
+    pub static ref BOT_CHANNELS: BotChannels = BotChannels::new(&data_path(BOT_CHANNELS_FILE));
+}
+
+#[derive(Debug)]
+pub struct BotChannels {
+    channels: JsonStore<HashMap<String, HashSet<u64>>>,
+}
+
+impl BotChannels {
+    fn new(name: &str) -> Self {
+        BotChannels { channels: JsonStore::load(name) }
+    }
+
+    /// Returns whether commands may be used in the channel of the guild.
+    pub fn is_allowed(&self, guild_id: GuildId, channel_id: ChannelId) -> bool {
+        self.channels.read(|channels| {
+            match channels.get(&guild_id.to_string()) {
+                Some(channels) if !channels.is_empty() => channels.contains(&channel_id.0),
+                _ => true,
+            }
+        })
+    }
+
+    /// Returns the channels to which the guild's commands are confined.
+    pub fn list(&self, guild_id: GuildId) -> Vec<ChannelId> {
+        let mut channels = self.channels.read(|channels| {
+            channels.get(&guild_id.to_string())
+                .map(|channels| channels.iter().cloned().collect::<Vec<u64>>())
+                .unwrap_or_else(Vec::new)
+        });
+        channels.sort();
+        channels.into_iter().map(ChannelId).collect()
+    }
+
+    /// Adds the channel to, or removes it from, those to which the guild's
+    /// commands are confined, then persists the change. Returns whether the
+    /// channels changed.
+    pub fn set(&self, guild_id: GuildId, channel_id: ChannelId, allowed: bool) -> Result<bool> {
+        self.channels.modify(|channels| {
+            let key = guild_id.to_string();
+            if allowed {
+                return Ok(channels.entry(key).or_insert_with(HashSet::new).insert(channel_id.0));
+            }
+
+            let (changed, now_empty) = match channels.get_mut(&key) {
+                Some(guild_channels) => {
+                    (guild_channels.remove(&channel_id.0), guild_channels.is_empty())
+                },
+                None => (false, false),
+            };
+            if now_empty {
+                channels.remove(&key);
+            }
+            Ok(changed)
+        })
+    }
+}
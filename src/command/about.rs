@@ -0,0 +1,47 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `about` command.
+
+use ::{UPTIME, current_config};
+use chrono::UTC;
+use serenity::client::CACHE;
+use serenity::constants::USER_AGENT;
+use util::{check_msg, duration_to_string, random_colour};
+
+command!(about(context, message, _args) {
+    let guilds_count = match CACHE.read() {
+        Ok(cache) => cache.guilds.len(),
+        Err(_) => return Err("Failed to lock cache".to_owned()),
+    };
+    let uptime = UTC::now() - *UPTIME;
+
+    let config = current_config();
+    let colour = random_colour();
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            e.title(&format!("About {}", config.bot_name))
+                .colour(colour)
+                .field(|f| f.name("Version").value(env!("CARGO_PKG_VERSION")))
+                .field(|f| f.name("Serenity Version").value(serenity_version()))
+                .field(|f| f.name("Uptime").value(&duration_to_string(&uptime)))
+                .field(|f| f.name("Servers").value(&guilds_count.to_string()))
+                .field(|f| f.name("Source").value(&config.source_url))
+        })
+    }));
+});
+
+// Serenity doesn't expose its version directly, but includes it at the end of
+// the user agent it sends, e.g. `DiscordBot (https://..., 0.1.3)`.
+fn serenity_version() -> &'static str {
+    USER_AGENT.trim_right_matches(')')
+        .rsplit(", ")
+        .next()
+        .unwrap_or("unknown")
+}
@@ -0,0 +1,43 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `avatar` command, which shows the avatar of
+//! the mentioned user, or of the author of the message if nobody is mentioned.
+
+use serenity::model::User;
+use util::{check_msg, random_colour};
+
+command!(avatar(context, message, _args) {
+    let user = message.mentions.first().unwrap_or(&message.author);
+
+    let avatar_url = avatar_url(user);
+    let colour = random_colour();
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            e.title(&format!("{}'s avatar", user.name))
+                .colour(colour)
+                .url(&avatar_url)
+                .image(|i| i.url(&avatar_url))
+        })
+    }));
+});
+
+// Returns the URL of the user's avatar at its full size, or of the default
+// avatar Discord assigns them if they haven't set one.
+fn avatar_url(user: &User) -> String {
+    match user.avatar_url() {
+        Some(avatar_url) => format!("{}?size=1024", avatar_url),
+        None => {
+            // Discord picks one of five default avatars based on the user's
+            // discriminator.
+            let discriminator = user.discriminator.to_string().parse::<u16>().unwrap_or(0);
+            format!("https://cdn.discordapp.com/embed/avatars/{}.png", discriminator % 5)
+        },
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `ban` command, which bans the mentioned user
+//! from the server, e.g. `!ban @user [days] [reason]`. If given, the user's
+//! messages from the last `days` days are deleted as well.
+
+use serenity::model::permissions;
+use util::{bot_has_permissions, bot_id, check_msg, has_permissions, outranks, stringify};
+
+/// The maximum number of days of messages Discord allows to be deleted.
+const MAX_DELETE_MESSAGE_DAYS: u8 = 7;
+
+command!(ban(context, message, args) {
+    let guild_id = match message.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Err("This command can only be used in a server.".to_owned()),
+    };
+
+    let target = match message.mentions.first() {
+        Some(target) => target,
+        None => return Err("Please mention the user to ban.".to_owned()),
+    };
+
+    // The number of days is optional, so anything which isn't a number is the
+    // start of the reason instead.
+    let mut args = args.into_iter().skip(1).peekable();
+    let days = match args.peek().map(|days| days.parse::<u8>()) {
+        Some(Ok(days)) if days > MAX_DELETE_MESSAGE_DAYS => {
+            return Err(format!("Number of days of messages to delete cannot exceed {}",
+                               MAX_DELETE_MESSAGE_DAYS));
+        },
+        Some(Ok(days)) => days,
+        _ => 0,
+    };
+    if days > 0 || args.peek().map(String::as_ref) == Some("0") {
+        args.next();
+    }
+    let reason = args.collect::<Vec<String>>().join(" ");
+
+    if !has_permissions(guild_id, message.author.id, permissions::BAN_MEMBERS) {
+        return Err("You need the Ban Members permission to do that.".to_owned());
+    }
+    if !bot_has_permissions(guild_id, permissions::BAN_MEMBERS) {
+        return Err("I need the Ban Members permission to do that.".to_owned());
+    }
+
+    if !outranks(guild_id, message.author.id, target.id) {
+        return Err(format!("You cannot ban {} as they do not rank below you.", target.name));
+    }
+    if !bot_id().map_or(false, |bot_id| outranks(guild_id, bot_id, target.id)) {
+        return Err(format!("I cannot ban {} as they do not rank below me.", target.name));
+    }
+
+    context.ban(guild_id, target.id, days).map_err(stringify)?;
+    info!("User '{}' banned '{}' from guild {}, deleting {} days of messages: {}",
+          message.author.name,
+          target.name,
+          guild_id,
+          days,
+          reason);
+
+    check_msg(context.say(&if reason.is_empty() {
+        format!("Banned {}.", target.name)
+    } else {
+        format!("Banned {}. Reason: {}", target.name, reason)
+    }));
+});
@@ -0,0 +1,66 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `botchannel` command, which allows guild
+//! admins to confine the bot's commands to certain channels, e.g.
+//! `!botchannel add #bot-commands`.
+
+use bot_channels::BOT_CHANNELS;
+use serenity::model::ChannelId;
+use util::{check_msg, is_guild_admin};
+
+command!(botchannel(context, message, args) {
+    let guild_id = match message.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Err("Bot channels can only be set in a server.".to_owned()),
+    };
+
+    let mut args = args.into_iter();
+    let allowed = match args.next().as_ref().map(String::as_ref) {
+        Some("add") => true,
+        Some("remove") => false,
+        _ => {
+            let channels = BOT_CHANNELS.list(guild_id)
+                .into_iter()
+                .map(|channel_id| format!("<#{}>", channel_id.0))
+                .collect::<Vec<String>>();
+            check_msg(context.say(&if channels.is_empty() {
+                "Commands may be used in every channel of this server.".to_owned()
+            } else {
+                format!("Commands may only be used in: {}", channels.join(", "))
+            }));
+            return Ok(());
+        },
+    };
+
+    // Default to the current channel if none is mentioned.
+    let channel_id = match args.next() {
+        Some(channel) => {
+            match channel.trim_left_matches("<#").trim_right_matches('>').parse::<u64>() {
+                Ok(channel_id) => ChannelId(channel_id),
+                Err(_) => return Err("Please mention a channel (e.g. #bot-commands).".to_owned()),
+            }
+        },
+        None => message.channel_id,
+    };
+
+    if !is_guild_admin(guild_id, message.author.id) {
+        return Err("You do not have permission to do that.".to_owned());
+    }
+
+    let changed = BOT_CHANNELS.set(guild_id, channel_id, allowed)
+        .map_err(|err| format!("Failed to save bot channels: {}", err))?;
+
+    check_msg(context.say(&match (allowed, changed) {
+        (true, true) => format!("Commands may now be used in <#{}>.", channel_id.0),
+        (true, false) => format!("Commands may already be used in <#{}>.", channel_id.0),
+        (false, true) => format!("<#{}> is no longer a bot channel.", channel_id.0),
+        (false, false) => format!("<#{}> is not a bot channel.", channel_id.0),
+    }));
+});
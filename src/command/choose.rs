@@ -0,0 +1,31 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `choose` command, which picks one of several
+//! `|`-separated options at random, e.g. `!choose pizza | sushi | tacos`.
+
+use rand::{self, Rng};
+use util::check_msg;
+
+command!(choose(context, _message, args) {
+    let options = args.join(" ");
+    let options = options.split('|')
+        .map(str::trim)
+        .filter(|option| !option.is_empty())
+        .collect::<Vec<&str>>();
+
+    if options.len() < 2 {
+        return Err("Please specify at least two options, separated by `|` (e.g. a | b | c)"
+            .to_owned());
+    }
+
+    if let Some(option) = rand::thread_rng().choose(&options) {
+        check_msg(context.say(option));
+    }
+});
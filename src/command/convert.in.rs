@@ -0,0 +1,5 @@
+#[derive(Debug, Deserialize)]
+struct RatesResponse {
+    /// The value of one US dollar in each currency, keyed by currency code.
+    pub rates: HashMap<String, f64>,
+}
@@ -0,0 +1,189 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `convert` command, which converts an amount
+//! between units of length, mass or temperature, e.g. `!convert 5 km mi`, or
+//! between currencies, e.g. `!convert 20 usd eur`.
+//!
+//! Exchange rates are retrieved from [Open Exchange Rates][oxr], and cached for
+//! a few minutes.
+//!
+//! [oxr]: https://openexchangerates.org/
+
+use ::current_config;
+use chrono::{DateTime, Duration, UTC};
+use error::{Error, Result};
+use hyper::Url;
+use hyper::client::Client;
+use serde_json;
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::Mutex;
+use util::{check_msg, http_client, with_typing};
+
+const USAGE: &'static str = "Please specify an amount and the units to convert between (e.g. \
+                             convert 5 km mi, or convert 20 usd eur)";
+
+/// The number of minutes for which retrieved exchange rates are reused.
+const RATES_TTL_MINUTES: i64 = 10;
+
+lazy_static! {
+    static ref RATES: Mutex<Option<(DateTime<UTC>, HashMap<String, f64>)>> = Mutex::new(None);
+    static ref RATES_URL: Url = "https://openexchangerates.org/api/latest.json".parse::<Url>()
+        .unwrap();
+}
+
+#[cfg(feature = "nightly")]
+include!("convert.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/convert.rs"));
+
+/// A kind of quantity which can be converted between units by scaling alone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Dimension {
+    Length,
+    Mass,
+}
+
+command!(convert(context, message, args) {
+    let (amount, from, to) = match (args.get(0), args.get(1), args.get(2)) {
+        (Some(amount), Some(from), Some(to)) => (amount, from.to_lowercase(), to.to_lowercase()),
+        _ => return Err(USAGE.to_owned()),
+    };
+    let amount = match amount.parse::<f64>() {
+        Ok(amount) if amount.is_finite() => amount,
+        _ => return Err(format!("\"{}\" is not a valid amount", amount)),
+    };
+
+    let cannot_convert = || format!("Cannot convert {} to {}", from, to);
+    let result = if let Some(kelvin) = to_kelvin(amount, &from) {
+        match from_kelvin(kelvin, &to) {
+            Some(result) => format!("{} {}", format_number(result), to),
+            None => return Err(cannot_convert()),
+        }
+    } else if let Some((from_dimension, from_factor)) = linear_unit(&from) {
+        match linear_unit(&to) {
+            Some((to_dimension, to_factor)) if to_dimension == from_dimension => {
+                format!("{} {}", format_number(amount * from_factor / to_factor), to)
+            },
+            _ => return Err(cannot_convert()),
+        }
+    } else if is_currency_code(&from) && is_currency_code(&to) {
+        let (from, to) = (from.to_uppercase(), to.to_uppercase());
+        let rates = with_typing(context, message.channel_id, exchange_rates)?;
+        match (rates.get(&from), rates.get(&to)) {
+            (Some(from_rate), Some(to_rate)) => {
+                format!("{:.2} {}", amount / from_rate * to_rate, to)
+            },
+            (None, _) => return Err(format!("Unknown currency: {}", from)),
+            (_, None) => return Err(format!("Unknown currency: {}", to)),
+        }
+    } else {
+        return Err(format!("Unknown unit: {}", from));
+    };
+
+    check_msg(context.say(&format!("{} {} = {}", format_number(amount), args[1], result)));
+});
+
+// Returns the dimension of the unit, and the number of metres or kilograms in
+// one of it.
+fn linear_unit(unit: &str) -> Option<(Dimension, f64)> {
+    use self::Dimension::*;
+
+    Some(match unit {
+        "mm" => (Length, 0.001),
+        "cm" => (Length, 0.01),
+        "m" => (Length, 1.0),
+        "km" => (Length, 1000.0),
+        "in" => (Length, 0.0254),
+        "ft" => (Length, 0.3048),
+        "yd" => (Length, 0.9144),
+        "mi" => (Length, 1609.344),
+        "mg" => (Mass, 0.000001),
+        "g" => (Mass, 0.001),
+        "kg" => (Mass, 1.0),
+        "t" => (Mass, 1000.0),
+        "oz" => (Mass, 0.028349523125),
+        "lb" => (Mass, 0.45359237),
+        "st" => (Mass, 6.35029318),
+        _ => return None,
+    })
+}
+
+// Converts the temperature in the unit to kelvin, or returns `None` if the
+// unit is not one of temperature.
+fn to_kelvin(amount: f64, unit: &str) -> Option<f64> {
+    match unit {
+        "c" => Some(amount + 273.15),
+        "f" => Some((amount - 32.0) * 5.0 / 9.0 + 273.15),
+        "k" => Some(amount),
+        _ => None,
+    }
+}
+
+// Converts the temperature in kelvin to the unit, or returns `None` if the
+// unit is not one of temperature.
+fn from_kelvin(kelvin: f64, unit: &str) -> Option<f64> {
+    match unit {
+        "c" => Some(kelvin - 273.15),
+        "f" => Some((kelvin - 273.15) * 9.0 / 5.0 + 32.0),
+        "k" => Some(kelvin),
+        _ => None,
+    }
+}
+
+fn is_currency_code(code: &str) -> bool {
+    code.len() == 3 && code.chars().all(|c| c >= 'a' && c <= 'z')
+}
+
+// Formats the number with at most four decimal places, without trailing zeros.
+fn format_number(number: f64) -> String {
+    let formatted = format!("{:.4}", number);
+    formatted.trim_right_matches('0').trim_right_matches('.').to_owned()
+}
+
+// Returns the current exchange rates, retrieving them again if those cached
+// have expired.
+fn exchange_rates() -> ::std::result::Result<HashMap<String, f64>, String> {
+    let mut cached = RATES.lock().expect("Failed to lock exchange rates");
+    if let Some((retrieved_at, ref rates)) = *cached {
+        if UTC::now() - retrieved_at < Duration::minutes(RATES_TTL_MINUTES) {
+            return Ok(rates.clone());
+        }
+    }
+
+    let api_key = match current_config().currency_api_key {
+        Some(ref api_key) => api_key.clone(),
+        None => return Err("Currency conversion has not been configured.".to_owned()),
+    };
+    let rates = match query_rates(http_client(), &api_key) {
+        Ok(response) => response.rates,
+        Err(err) => {
+            warn!("Failed to query exchange rates: {}", err);
+            return Err("Failed to retrieve exchange rates, please try again later.".to_owned());
+        },
+    };
+
+    *cached = Some((UTC::now(), rates.clone()));
+    Ok(rates)
+}
+
+fn query_rates(client: &Client, api_key: &str) -> Result<RatesResponse> {
+    let mut url = RATES_URL.clone();
+    url.query_pairs_mut()
+        .clear()
+        .append_pair("app_id", api_key);
+
+    let mut response = client.get(url).send().map_err(Error::from)?;
+    let mut result = String::new();
+    response.read_to_string(&mut result).map_err(Error::from)?;
+
+    serde_json::from_str(&result).map_err(Error::from)
+}
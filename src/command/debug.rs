@@ -0,0 +1,74 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `debug` command, which allows the bot's
+//! owners to inspect its runtime state.
+
+use ::{CONFIG_PATH, UPTIME};
+use chrono::UTC;
+use counter::CommandCounter;
+use serenity::client::CACHE;
+use util::{check_msg, duration_to_string, is_owner, time_ago, timestamp_to_string_tz,
+           truncate_words};
+
+command!(debug(context, message, _args) {
+    if !is_owner(message.author.id) {
+        return Err("You do not have permission to do that.".to_owned());
+    }
+
+    let (guilds, channels, private_channels) = match CACHE.read() {
+        Ok(cache) => {
+            (cache.guilds.len(),
+             cache.guilds.values().map(|guild| guild.channels.len()).sum::<usize>(),
+             cache.private_channels.len())
+        },
+        Err(_) => return Err("Failed to lock cache".to_owned()),
+    };
+
+    let (counts, since, last_saved, users) = {
+        let data = context.data.lock().expect("Failed to lock context data");
+        let counter = data.get::<CommandCounter>().unwrap();
+        let mut counts = counter.commands
+            .iter()
+            .map(|(command, count)| format!("{}: {}", command, count))
+            .collect::<Vec<String>>();
+        counts.sort();
+        (counts, counter.since, counter.last_saved, counter.users.len())
+    };
+
+    let last_saved = last_saved.map_or_else(|| "Never".to_owned(), |last_saved| {
+        format!("{} ({})", time_ago(&last_saved), timestamp_to_string_tz(&last_saved))
+    });
+    let report = format!("Uptime:          {}\n\
+                          Config path:     {}\n\
+                          Cached guilds:   {}\n\
+                          Cached channels: {} guild, {} private\n\
+                          Counting since:  {}\n\
+                          Last saved:      {}\n\
+                          Commands with user counts: {}\n\
+                          \n\
+                          Command counts:\n{}",
+                         duration_to_string(&(UTC::now() - *UPTIME)),
+                         *CONFIG_PATH,
+                         guilds,
+                         channels,
+                         private_channels,
+                         timestamp_to_string_tz(&since),
+                         last_saved,
+                         users,
+                         if counts.is_empty() {
+                             "None".to_owned()
+                         } else {
+                             counts.join("\n")
+                         });
+
+    // Discord's message length limit, less the code block's formatting.
+    let report = truncate_words(&report, 1990).replace('`', "`\u{200B}");
+    check_msg(context.say(&format!("```\n{}\n```", report)));
+});
@@ -0,0 +1,57 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `8ball` command, which answers a question
+//! like a [Magic 8-Ball][magic-8-ball].
+//!
+//! [magic-8-ball]: https://en.wikipedia.org/wiki/Magic_8-Ball
+
+use rand::{self, Rng};
+use util::{check_msg, random_colour};
+
+/// The answers of the original Magic 8-Ball.
+const ANSWERS: &'static [&'static str] = &[
+    "It is certain.",
+    "It is decidedly so.",
+    "Without a doubt.",
+    "Yes, definitely.",
+    "You may rely on it.",
+    "As I see it, yes.",
+    "Most likely.",
+    "Outlook good.",
+    "Yes.",
+    "Signs point to yes.",
+    "Reply hazy, try again.",
+    "Ask again later.",
+    "Better not tell you now.",
+    "Cannot predict now.",
+    "Concentrate and ask again.",
+    "Don't count on it.",
+    "My reply is no.",
+    "My sources say no.",
+    "Outlook not so good.",
+    "Very doubtful.",
+];
+
+command!(eightball(context, message, args) {
+    let question = args.join(" ");
+    if question.trim().is_empty() {
+        return Err("Please ask a question (e.g. 8ball Will it rain tomorrow?)".to_owned());
+    }
+
+    let answer = rand::thread_rng().choose(ANSWERS).unwrap_or(&ANSWERS[0]);
+    let colour = random_colour();
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            e.colour(colour)
+                .title(question.trim())
+                .description(answer)
+        })
+    }));
+});
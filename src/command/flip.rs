@@ -0,0 +1,40 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `flip` command, which flips one or more
+//! coins, e.g. `!flip` or `!flip 5`.
+
+use ::current_config;
+use rand::{self, Rng};
+use util::check_msg;
+
+command!(flip(context, _message, args) {
+    let max_flips = current_config().max_flips;
+    let count = match args.first().map(|count| count.parse::<u32>()) {
+        None => 1,
+        Some(Ok(0)) | Some(Err(_)) => {
+            return Err("Please specify a positive number of coins to flip.".to_owned());
+        },
+        Some(Ok(count)) if count > max_flips => {
+            return Err(format!("Number of coins cannot exceed {}", max_flips));
+        },
+        Some(Ok(count)) => count,
+    };
+
+    let mut rng = rand::thread_rng();
+    let heads = (0..count).filter(|_| rng.gen::<bool>()).count();
+
+    let response = if count == 1 {
+        let side = if heads == 1 { "Heads" } else { "Tails" };
+        side.to_owned()
+    } else {
+        format!("Heads: {}, Tails: {}", heads, count as usize - heads)
+    };
+    check_msg(context.say(&response));
+});
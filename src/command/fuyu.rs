@@ -12,53 +12,184 @@
 //! [Markov chain][markov-chain] out of the data using the [markov][markov-lib]
 //! library. It then returns a message generated by the Markov chain.
 //!
+//! Each `.txt` file in the `logs` directory is a separate corpus, named after
+//! the file. `!fuyu <corpus> [seed]` generates a message from the named corpus,
+//! optionally starting with the seed word, while `!fuyu [seed]` uses the
+//! default `fuyu` corpus (`logs/fuyu.txt`). The first argument is only taken
+//! to be a corpus if one by that name exists.
+//!
 //! [markov-lib]: https://github.com/aatxe/markov
 //! [markov-chain]: https://en.wikipedia.org/wiki/Markov_chain
 
 extern crate markov;
 
+use ::current_config;
 use self::markov::Chain;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use util::{check_msg, is_owner, random_colour, truncate_words, with_typing};
+
+/// The name of the corpus used when none is specified.
+const DEFAULT_CORPUS: &'static str = "fuyu";
 
-use util::{check_msg, random_colour, stringify};
+lazy_static! {
+    // Building a chain requires reading and feeding in its entire corpus, so
+    // it is only done once, or on an explicit reload.
+    static ref CHAINS: RwLock<HashMap<String, Chain<String>>> = RwLock::new(create_chains());
+}
+
+command!(fuyu(context, message, args) {
+    if args.first().map(String::as_ref) == Some("reload") {
+        if !is_owner(message.author.id) {
+            return Err("You do not have permission to do that.".to_owned());
+        }
+
+        *CHAINS.write().expect("Failed to lock chains") = create_chains();
+        check_msg(context.say("Chat logs reloaded."));
+        return Ok(());
+    }
 
-command!(fuyu(context, message, _args) {
     let channel_id = message.channel_id;
-    context.broadcast_typing(channel_id).map_err(stringify)?;
+    let mut args = args.into_iter().peekable();
+    let is_corpus = args.peek().map_or(false, |arg| {
+        CHAINS.read().expect("Failed to lock chains").contains_key(arg)
+    });
+    let (corpus, seed) = match args.next() {
+        Some(corpus) if is_corpus => (corpus, args.next()),
+        // Otherwise it is the seed for the default corpus, e.g. `!fuyu hello`.
+        seed => (DEFAULT_CORPUS.to_owned(), seed),
+    };
+
+    let (response, note) = with_typing(context, channel_id, || generate(&corpus, seed))?;
 
-    let response = create_chain().generate_str();
+    let response = truncate_words(&response, current_config().fuyu_max_len);
     let colour = random_colour();
-    check_msg(context.send_message(
-        channel_id,
-        |m| m.embed(|e| e.colour(colour).description(response.as_ref())),
-    ));
+    check_msg(context.send_message(channel_id, |m| {
+        m.embed(|e| {
+            let e = e.colour(colour).description(response.as_ref());
+            match note {
+                Some(ref note) => e.footer(|f| f.text(note)),
+                None => e,
+            }
+        })
+    }));
 });
 
-fn create_chain() -> Chain<String> {
-    let chat_logs = load_chat_logs();
-    let mut chain = Chain::new();
-    for line in chat_logs.split("\n\n") {
+// Generates a message from the chat log, starting with the seed if it appears
+// in it. If not, a note saying so is also returned.
+fn generate(corpus: &str, seed: Option<String>) -> Result<(String, Option<String>), String> {
+    let chains = CHAINS.read().expect("Failed to lock chains");
+    let chain = match chains.get(corpus) {
+        Some(chain) => chain,
+        None => {
+            let mut corpora = chains.keys().cloned().collect::<Vec<String>>();
+            corpora.sort();
+            return Err(if corpora.is_empty() {
+                "No chat logs available.".to_owned()
+            } else {
+                format!("Unknown chat log \"{}\". Available chat logs: {}",
+                        corpus,
+                        corpora.join(", "))
+            });
+        },
+    };
+
+    Ok(match seed {
+        Some(seed) => {
+            // The chain yields nothing if the seed never appears in it.
+            let response = chain.generate_str_from_token(&seed);
+            if response.is_empty() {
+                (chain.generate_str(),
+                 Some(format!("\"{}\" does not appear in the chat logs.", seed)))
+            } else {
+                (response, None)
+            }
+        },
+        None => (chain.generate_str(), None),
+    })
+}
+
+fn create_chains() -> HashMap<String, Chain<String>> {
+    load_chat_logs()
+        .into_iter()
+        .map(|(corpus, chat_logs)| {
+            let chain = create_chain(&corpus, &chat_logs);
+            (corpus, chain)
+        })
+        .collect()
+}
+
+fn create_chain(corpus: &str, chat_logs: &str) -> Chain<String> {
+    let lines = chat_logs.split("\n\n").collect::<Vec<&str>>();
+
+    let mut order = current_config().fuyu_order;
+    if order < 1 {
+        warn!("Invalid fuyu_order {}, using an order of 1 instead", order);
+        order = 1;
+    }
+
+    // An order which approaches the length of the average line leaves the
+    // chain with very few choices, so it ends up parroting the corpus.
+    let words = lines.iter().map(|line| line.split_whitespace().count()).sum::<usize>();
+    if !lines.is_empty() && order >= words / lines.len() {
+        warn!("fuyu_order {} is high for corpus \"{}\", which averages {} words per line",
+              order,
+              corpus,
+              words / lines.len());
+    }
+
+    let mut chain = Chain::of_order(order);
+    for line in lines {
         chain.feed_str(line);
     }
     chain
 }
 
 #[cfg(feature = "fuyu-include")]
-fn load_chat_logs() -> String {
+fn load_chat_logs() -> HashMap<String, String> {
     const FUYU_CHAT_LOGS: &'static str = include_str!("../../logs/fuyu.txt");
 
-    FUYU_CHAT_LOGS.to_owned()
+    let mut chat_logs = HashMap::new();
+    chat_logs.insert(DEFAULT_CORPUS.to_owned(), FUYU_CHAT_LOGS.to_owned());
+    chat_logs
 }
 
 #[cfg(not(feature = "fuyu-include"))]
-fn load_chat_logs() -> String {
-    use std::fs::File;
+fn load_chat_logs() -> HashMap<String, String> {
+    use std::fs::{self, File};
     use std::io::Read;
+    use util::data_path;
+
+    const DIRECTORY_NAME: &'static str = "logs";
 
-    const FILE_NAME: &'static str = "logs/fuyu.txt";
+    let directory = data_path(DIRECTORY_NAME);
+    let mut chat_logs = HashMap::new();
+    let entries = match fs::read_dir(&directory) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("Failed to read chat log directory \"{}\": {}", directory, err);
+            return chat_logs;
+        },
+    };
 
-    let mut file = File::open(FILE_NAME).expect("Failed to open chat log file");
-    let mut contents = String::new();
-    file.read_to_string(&mut contents).expect("Failed to read chat log file");
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if path.extension().and_then(|ext| ext.to_str()) != Some("txt") {
+            continue;
+        }
+        let corpus = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(corpus) => corpus.to_owned(),
+            None => continue,
+        };
+
+        let mut contents = String::new();
+        match File::open(&path).and_then(|mut file| file.read_to_string(&mut contents)) {
+            Ok(_) => {
+                chat_logs.insert(corpus, contents);
+            },
+            Err(err) => warn!("Failed to read chat log file {}: {}", path.display(), err),
+        }
+    }
 
-    contents
+    chat_logs
 }
@@ -0,0 +1,83 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `help` command, which lists the available
+//! commands by category, or describes a single command, e.g. `!help roll`.
+//!
+//! Only the commands enabled at compile time are listed.
+
+use ::current_config;
+use command::registry::commands;
+use prefix::PREFIXES;
+use util::{check_msg, random_colour};
+
+/// The order in which categories are listed.
+const CATEGORIES: &'static [&'static str] = &["Fun",
+                                              "Information",
+                                              "Utility",
+                                              "Moderation",
+                                              "Server Settings",
+                                              "Owner"];
+
+command!(help(context, message, args) {
+    let commands = commands();
+    let prefix = message.guild_id()
+        .and_then(|guild_id| PREFIXES.get(guild_id))
+        .unwrap_or_else(|| current_config().command_prefix.clone());
+    let colour = random_colour();
+
+    if let Some(name) = args.first() {
+        let name = name.trim_left_matches(&*prefix).to_lowercase();
+        let command = match commands.iter().find(|command| command.name == name) {
+            Some(command) => *command,
+            None => return Err(format!("Unknown command: {}", name)),
+        };
+
+        let usage = if command.usage.is_empty() {
+            format!("`{}{}`", prefix, command.name)
+        } else {
+            format!("`{}{} {}`", prefix, command.name, command.usage)
+        };
+        check_msg(context.send_message(message.channel_id, |m| {
+            m.embed(|e| {
+                e.title(command.name)
+                    .colour(colour)
+                    .description(command.description)
+                    .field(|f| f.name("Usage").value(&usage))
+                    .footer(|f| f.text(command.category))
+            })
+        }));
+        return Ok(());
+    }
+
+    let categories = CATEGORIES.iter()
+        .map(|category| {
+            let lines = commands.iter()
+                .filter(|command| command.category == *category)
+                .map(|command| format!("`{}` - {}", command.name, command.description))
+                .collect::<Vec<String>>();
+            (*category, lines.join("\n"))
+        })
+        .filter(|&(_, ref lines)| !lines.is_empty())
+        .collect::<Vec<(&str, String)>>();
+
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            let e = e.title("Commands")
+                .colour(colour)
+                .footer(|f| {
+                    f.text(&format!("Use {}help <command> for more information on a command.",
+                                    prefix))
+                });
+            categories.iter().fold(e, |e, &(category, ref lines)| {
+                e.field(|f| f.name(category).value(lines).inline(false))
+            })
+        })
+    }));
+});
@@ -0,0 +1,54 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `kick` command, which kicks the mentioned
+//! member from the server, e.g. `!kick @user [reason]`.
+
+use serenity::model::permissions;
+use util::{bot_has_permissions, bot_id, check_msg, has_permissions, outranks, stringify};
+
+command!(kick(context, message, args) {
+    let guild_id = match message.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Err("This command can only be used in a server.".to_owned()),
+    };
+
+    let target = match message.mentions.first() {
+        Some(target) => target,
+        None => return Err("Please mention the user to kick.".to_owned()),
+    };
+    let reason = args.iter().skip(1).cloned().collect::<Vec<String>>().join(" ");
+
+    if !has_permissions(guild_id, message.author.id, permissions::KICK_MEMBERS) {
+        return Err("You need the Kick Members permission to do that.".to_owned());
+    }
+    if !bot_has_permissions(guild_id, permissions::KICK_MEMBERS) {
+        return Err("I need the Kick Members permission to do that.".to_owned());
+    }
+
+    if !outranks(guild_id, message.author.id, target.id) {
+        return Err(format!("You cannot kick {} as they do not rank below you.", target.name));
+    }
+    if !bot_id().map_or(false, |bot_id| outranks(guild_id, bot_id, target.id)) {
+        return Err(format!("I cannot kick {} as they do not rank below me.", target.name));
+    }
+
+    context.kick_member(guild_id, target.id).map_err(stringify)?;
+    info!("User '{}' kicked '{}' from guild {}: {}",
+          message.author.name,
+          target.name,
+          guild_id,
+          reason);
+
+    check_msg(context.say(&if reason.is_empty() {
+        format!("Kicked {}.", target.name)
+    } else {
+        format!("Kicked {}. Reason: {}", target.name, reason)
+    }));
+});
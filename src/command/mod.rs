@@ -7,16 +7,70 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+#[cfg(feature = "about")]
+pub mod about;
+#[cfg(feature = "avatar")]
+pub mod avatar;
+#[cfg(feature = "ban")]
+pub mod ban;
+#[cfg(feature = "botchannel")]
+pub mod botchannel;
+#[cfg(feature = "choose")]
+pub mod choose;
+#[cfg(feature = "convert")]
+pub mod convert;
+#[cfg(feature = "debug")]
+pub mod debug;
+#[cfg(feature = "eightball")]
+pub mod eightball;
+#[cfg(feature = "flip")]
+pub mod flip;
 #[cfg(feature = "fuyu")]
 pub mod fuyu;
+#[cfg(feature = "help")]
+pub mod help;
+#[cfg(feature = "kick")]
+pub mod kick;
+pub mod pagination;
 #[cfg(feature = "ping")]
 pub mod ping;
+#[cfg(feature = "poll")]
+pub mod poll;
+#[cfg(feature = "prefix")]
+pub mod prefix;
+#[cfg(feature = "purge")]
+pub mod purge;
+pub mod registry;
+#[cfg(feature = "reload")]
+pub mod reload;
+#[cfg(feature = "remind")]
+pub mod remind;
+#[cfg(feature = "role")]
+pub mod role;
 #[cfg(feature = "roll")]
 pub mod roll;
+#[cfg(feature = "say")]
+pub mod say;
+#[cfg(feature = "serverinfo")]
+pub mod serverinfo;
+#[cfg(feature = "shutdown")]
+pub mod shutdown;
 #[cfg(feature = "stats")]
 pub mod stats;
 #[cfg(feature = "tag")]
 pub mod tag;
+#[cfg(feature = "toggle")]
+pub mod toggle;
+#[cfg(feature = "uptime")]
+pub mod uptime;
+#[cfg(feature = "urban")]
+pub mod urban;
+#[cfg(feature = "userinfo")]
+pub mod userinfo;
+#[cfg(feature = "weather")]
+pub mod weather;
+#[cfg(feature = "welcome")]
+pub mod welcome;
 #[cfg(feature = "wolfram")]
 pub mod wolfram_alpha;
 #[cfg(feature = "xkcd")]
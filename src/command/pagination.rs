@@ -0,0 +1,173 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Posts embeds with several pages, which the user who asked for them may flip
+//! through by reacting with ◀ and ▶. The message is edited in place.
+//!
+//! Once a few minutes have passed without anyone flipping a page, the
+//! navigation reactions are removed and the message is left as it is.
+
+use serenity::client::Context;
+use serenity::model::{ChannelId, Message, MessageId, Reaction, ReactionType, UserId};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use util::{bot_id, check_msg};
+
+/// The reaction which flips to the previous page.
+const PREVIOUS: &'static str = "◀";
+/// The reaction which flips to the next page.
+const NEXT: &'static str = "▶";
+/// The number of seconds after the last page flip at which the navigation is
+/// removed.
+const TIMEOUT: u64 = 180;
+
+lazy_static! {
+    static ref PAGINATED: Mutex<HashMap<MessageId, Paginated>> = Mutex::new(HashMap::new());
+}
+
+/// A message whose pages are being flipped through.
+#[derive(Debug)]
+struct Paginated {
+    channel_id: ChannelId,
+    /// The user who may flip the pages.
+    user_id: UserId,
+    title: String,
+    pages: Vec<String>,
+    /// The index of the page being shown.
+    page: usize,
+    last_flipped: Instant,
+}
+
+impl Paginated {
+    fn edit(&self, message_id: MessageId) {
+        let description = &self.pages[self.page];
+        let footer = self.footer();
+        check_msg(self.channel_id.edit_message(message_id, |m| {
+            m.embed(|e| {
+                e.title(&self.title)
+                    .description(description)
+                    .footer(|f| f.text(&footer))
+            })
+        }));
+    }
+
+    fn footer(&self) -> String {
+        format!("Page {} of {}", self.page + 1, self.pages.len())
+    }
+}
+
+/// Posts the embed with the page at `page` in response to the message, adding
+/// the navigation reactions if there is more than one page.
+pub fn send(context: &Context, message: &Message, title: &str, pages: Vec<String>, page: usize) {
+    let paginated = Paginated {
+        channel_id: message.channel_id,
+        user_id: message.author.id,
+        title: title.to_owned(),
+        pages: pages,
+        page: page,
+        last_flipped: Instant::now(),
+    };
+
+    let footer = paginated.footer();
+    let sent = context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            e.title(title)
+                .description(&paginated.pages[page])
+                .footer(|f| f.text(&footer))
+        })
+    });
+    let sent = match sent {
+        Ok(sent) => sent,
+        Err(err) => {
+            check_msg(Err(err));
+            return;
+        },
+    };
+    if paginated.pages.len() < 2 {
+        return;
+    }
+
+    for emoji in &[PREVIOUS, NEXT] {
+        let reaction = ReactionType::Unicode((*emoji).to_owned());
+        if let Err(err) = context.create_reaction(sent.channel_id, sent.id, reaction) {
+            warn!("Failed to add page navigation: {:?}", err);
+            return;
+        }
+    }
+
+    PAGINATED.lock().expect("Failed to lock paginated messages").insert(sent.id, paginated);
+    expire_later(sent.id, TIMEOUT);
+}
+
+/// Flips the page of the paginated message reacted to, if the reaction is one
+/// of the navigation reactions and was made by the user who may flip it.
+pub fn handle_reaction(reaction: &Reaction) {
+    let forward = match reaction.emoji {
+        ReactionType::Unicode(ref unicode) if unicode == PREVIOUS => false,
+        ReactionType::Unicode(ref unicode) if unicode == NEXT => true,
+        _ => return,
+    };
+    if Some(reaction.user_id) == bot_id() {
+        return;
+    }
+
+    {
+        let mut paginated = PAGINATED.lock().expect("Failed to lock paginated messages");
+        let paginated = match paginated.get_mut(&reaction.message_id) {
+            Some(paginated) if paginated.user_id == reaction.user_id => paginated,
+            _ => return,
+        };
+
+        let pages = paginated.pages.len();
+        paginated.page = if forward {
+            (paginated.page + 1) % pages
+        } else {
+            (paginated.page + pages - 1) % pages
+        };
+        paginated.last_flipped = Instant::now();
+        paginated.edit(reaction.message_id);
+    }
+
+    // Remove the user's reaction, so that they can react again to flip
+    // another page. This requires the Manage Messages permission, without
+    // which the user must remove it themselves.
+    let _ = reaction.channel_id.delete_reaction(reaction.message_id,
+                                                Some(reaction.user_id),
+                                                reaction.emoji.clone());
+}
+
+// Removes the navigation of the message once it has gone `TIMEOUT` seconds
+// without a page being flipped, checking again after `delay` seconds.
+fn expire_later(message_id: MessageId, delay: u64) {
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(delay));
+
+        let expired = {
+            let mut paginated = PAGINATED.lock().expect("Failed to lock paginated messages");
+            let elapsed = match paginated.get(&message_id) {
+                Some(paginated) => paginated.last_flipped.elapsed().as_secs(),
+                None => return,
+            };
+            if elapsed < TIMEOUT {
+                expire_later(message_id, TIMEOUT - elapsed);
+                return;
+            }
+            paginated.remove(&message_id)
+        };
+
+        if let Some(expired) = expired {
+            for emoji in &[PREVIOUS, NEXT] {
+                let reaction = ReactionType::Unicode((*emoji).to_owned());
+                let _ = expired.channel_id.delete_reaction(message_id, None, reaction);
+            }
+        }
+    });
+}
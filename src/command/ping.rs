@@ -11,12 +11,55 @@ extern crate time;
 
 use self::time::PreciseTime;
 
-command!(ping(context, _message, _args) {
+/// The maximum number of round-trips a single ping may measure.
+const MAX_SAMPLES: usize = 5;
+
+command!(ping(context, _message, args) {
+    let samples = match args.first().map(|samples| samples.parse::<usize>()) {
+        Some(Ok(samples)) if samples >= 1 && samples <= MAX_SAMPLES => samples,
+        Some(_) => return Err(format!("The sample count must be between 1 and {}.", MAX_SAMPLES)),
+        None => 1,
+    };
+
     let start = PreciseTime::now();
     let msg = context.say("0");
     let end = PreciseTime::now();
     if let Ok(mut m) = msg {
-        let ms = start.to(end).num_milliseconds();
-        let _ = m.edit(&format!("Pong, {} milliseconds", ms), |m| m);
+        // Further samples time edits of the message, rather than sending more.
+        let mut latencies = vec![start.to(end).num_milliseconds()];
+        for sample in 1..samples {
+            let start = PreciseTime::now();
+            if m.edit(&sample.to_string(), |m| m).is_err() {
+                break;
+            }
+            latencies.push(start.to(PreciseTime::now()).num_milliseconds());
+        }
+
+        // The shard only knows its latency once it has received at least one
+        // heartbeat acknowledgement.
+        let heartbeat = context.shard
+            .lock()
+            .ok()
+            .and_then(|shard| shard.latency())
+            .map(|latency| {
+                let ms = latency.as_secs() * 1000 + u64::from(latency.subsec_nanos()) / 1_000_000;
+                format!("{} milliseconds", ms)
+            })
+            .unwrap_or_else(|| "N/A".to_owned());
+
+        let pong = if latencies.len() == 1 {
+            format!("Pong, {} milliseconds", latencies[0])
+        } else {
+            let min = latencies.iter().min().cloned().unwrap_or(0);
+            let max = latencies.iter().max().cloned().unwrap_or(0);
+            #[cfg_attr(feature = "clippy", allow(cast_precision_loss))]
+            let avg = latencies.iter().sum::<i64>() as f64 / latencies.len() as f64;
+            format!("Pong, {} samples: min {} / avg {:.1} / max {} milliseconds",
+                    latencies.len(),
+                    min,
+                    avg,
+                    max)
+        };
+        let _ = m.edit(&format!("{}\nHeartbeat: {}", pong, heartbeat), |m| m);
     }
 });
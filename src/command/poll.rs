@@ -0,0 +1,123 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `poll` command, which posts a question along
+//! with its options, e.g. `!poll "Question" "Option A" "Option B"`, and adds a
+//! reaction for each option so that users can vote on them.
+//!
+//! `!poll results <message id>` tallies the votes of a poll in the channel.
+
+use serenity::client::Context;
+use serenity::model::{Message, MessageId, ReactionType};
+use util::{check_msg, random_colour, split_quoted, stringify};
+
+/// The reactions used to vote for each option, in order.
+const OPTION_EMOJI: &'static [&'static str] = &["1\u{20e3}", "2\u{20e3}", "3\u{20e3}",
+                                                  "4\u{20e3}", "5\u{20e3}", "6\u{20e3}",
+                                                  "7\u{20e3}", "8\u{20e3}", "9\u{20e3}",
+                                                  "\u{1f51f}"];
+
+const USAGE: &'static str = "Please specify a question and at least two options, each in quotes \
+                             (e.g. poll \"Pizza or tacos?\" \"Pizza\" \"Tacos\")";
+
+command!(poll(context, message, args) {
+    if args.first().map(String::as_ref) == Some("results") {
+        return results(context, message, args.get(1));
+    }
+
+    let mut args = split_quoted(&args.join(" ")).into_iter();
+    let question = match args.next() {
+        Some(question) => question,
+        None => return Err(USAGE.to_owned()),
+    };
+    let options = args.collect::<Vec<String>>();
+    if options.len() < 2 {
+        return Err(USAGE.to_owned());
+    }
+    if options.len() > OPTION_EMOJI.len() {
+        return Err(format!("Polls are limited to {} options", OPTION_EMOJI.len()));
+    }
+
+    let description = OPTION_EMOJI.iter()
+        .zip(options.iter())
+        .map(|(emoji, option)| format!("{} {}", emoji, option))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let colour = random_colour();
+    let poll = context.send_message(message.channel_id, |m| {
+            m.embed(|e| {
+                e.title(&question)
+                    .colour(colour)
+                    .description(&description)
+                    .footer(|f| f.text("Vote by reacting with the option's number."))
+            })
+        })
+        .map_err(stringify)?;
+
+    for emoji in OPTION_EMOJI.iter().take(options.len()) {
+        let reaction = ReactionType::Unicode((*emoji).to_owned());
+        if let Err(err) = context.create_reaction(poll.channel_id, poll.id, reaction) {
+            return Err(format!("Failed to add reactions to the poll: {:?}", err));
+        }
+    }
+});
+
+/// Tallies the votes on the poll with the specified message ID.
+fn results(context: &Context, message: &Message, poll_id: Option<&String>) -> Result<(), String> {
+    let poll_id = match poll_id.map(|id| id.parse::<u64>()) {
+        Some(Ok(poll_id)) => MessageId(poll_id),
+        _ => return Err("Please specify the message ID of the poll.".to_owned()),
+    };
+
+    let poll = match context.get_message(message.channel_id, poll_id) {
+        Ok(poll) => poll,
+        Err(_) => return Err("No message with that ID was found in this channel.".to_owned()),
+    };
+    let (question, description) = match poll.embeds.first() {
+        Some(embed) if poll.author.bot => {
+            (embed.title.clone().unwrap_or_else(String::new),
+             embed.description.clone().unwrap_or_else(String::new))
+        },
+        _ => return Err("That message is not a poll.".to_owned()),
+    };
+
+    // Each line of the poll is an option, preceded by the emoji used to vote
+    // for it. The bot's own reaction is not a vote.
+    let results = description.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, ' ');
+            match (parts.next(), parts.next()) {
+                (Some(emoji), Some(option)) => Some((emoji, option)),
+                _ => None,
+            }
+        })
+        .map(|(emoji, option)| {
+            let votes = poll.reactions
+                .iter()
+                .find(|reaction| match reaction.reaction_type {
+                    ReactionType::Unicode(ref unicode) => unicode == emoji,
+                    _ => false,
+                })
+                .map(|reaction| if reaction.me { reaction.count - 1 } else { reaction.count })
+                .unwrap_or(0);
+            format!("{}: {} votes", option, votes)
+        })
+        .collect::<Vec<String>>();
+
+    let colour = random_colour();
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            e.title(&format!("Results: {}", question))
+                .colour(colour)
+                .description(&results.join("\n"))
+        })
+    }));
+
+    Ok(())
+}
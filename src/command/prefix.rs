@@ -0,0 +1,64 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `prefix` command, which allows guild admins
+//! to choose a command prefix for their guild.
+
+use ::current_config;
+use prefix::PREFIXES;
+use util::{check_msg, is_guild_admin};
+
+/// The maximum number of characters a guild's prefix may contain.
+const MAX_PREFIX_LENGTH: usize = 5;
+
+command!(prefix(context, message, args) {
+    let guild_id = match message.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Err("Prefixes can only be set in a server.".to_owned()),
+    };
+
+    let mut args = args.into_iter();
+    let prefix = match args.next().as_ref().map(String::as_ref) {
+        Some("set") => {
+            match args.next() {
+                Some(prefix) => {
+                    if prefix.chars().count() > MAX_PREFIX_LENGTH {
+                        return Err(format!("Prefix limit is {} characters", MAX_PREFIX_LENGTH));
+                    }
+                    Some(prefix)
+                },
+                None => return Err("Please specify a prefix to use.".to_owned()),
+            }
+        },
+        Some("reset") => None,
+        _ => {
+            let response = match PREFIXES.get(guild_id) {
+                Some(prefix) => format!("This server's prefix is: {}", prefix),
+                None => {
+                    format!("This server uses the default prefix: {}",
+                            current_config().command_prefix)
+                },
+            };
+            check_msg(context.say(&response));
+            return Ok(());
+        },
+    };
+
+    if !is_guild_admin(guild_id, message.author.id) {
+        return Err("You do not have permission to do that.".to_owned());
+    }
+
+    PREFIXES.set(guild_id, prefix.clone())
+        .map_err(|err| format!("Failed to save prefix: {}", err))?;
+
+    check_msg(context.say(&match prefix {
+        Some(prefix) => format!("Prefix set to: {}", prefix),
+        None => "Prefix reset to the default.".to_owned(),
+    }));
+});
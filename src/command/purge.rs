@@ -0,0 +1,68 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `purge` command, which deletes the most
+//! recent messages in a channel, e.g. `!purge 20`.
+
+use chrono::{Duration, UTC};
+use serenity::model::{MessageId, permissions};
+use util::{bot_has_permissions, check_msg, has_permissions, snowflake_to_timestamp, stringify};
+
+/// The maximum number of messages Discord allows to be fetched or deleted in a
+/// single request.
+const MAX_MESSAGES: u64 = 100;
+
+command!(purge(context, message, args) {
+    let guild_id = match message.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Err("This command can only be used in a server.".to_owned()),
+    };
+
+    let count = match args.first().map(|count| count.parse::<u64>()) {
+        Some(Ok(count)) if count > 0 => count,
+        _ => return Err("Please specify a positive number of messages to delete.".to_owned()),
+    };
+    let clamped = count > MAX_MESSAGES;
+    let count = if clamped { MAX_MESSAGES } else { count };
+
+    if !has_permissions(guild_id, message.author.id, permissions::MANAGE_MESSAGES) {
+        return Err("You need the Manage Messages permission to do that.".to_owned());
+    }
+    if !bot_has_permissions(guild_id, permissions::MANAGE_MESSAGES) {
+        return Err("I need the Manage Messages permission to do that.".to_owned());
+    }
+
+    let channel_id = message.channel_id;
+    let messages = context.get_messages(channel_id, |g| g.before(message.id).limit(count))
+        .map_err(stringify)?;
+
+    // Discord refuses to bulk delete messages older than two weeks.
+    let cutoff = UTC::now() - Duration::days(14);
+    let (deletable, too_old): (Vec<MessageId>, Vec<MessageId>) = messages.iter()
+        .map(|message| message.id)
+        .partition(|id| snowflake_to_timestamp(id.0) > cutoff);
+
+    match deletable.len() {
+        0 => {},
+        // Bulk deletion requires at least two messages.
+        1 => context.delete_message(channel_id, deletable[0]).map_err(stringify)?,
+        _ => context.delete_messages(channel_id, &deletable).map_err(stringify)?,
+    }
+
+    let mut response = format!("Deleted {} messages.", deletable.len());
+    if clamped {
+        response.push_str(&format!(" At most {} messages can be deleted at once.", MAX_MESSAGES));
+    }
+    if !too_old.is_empty() {
+        response.push_str(&format!(" {} messages were older than 14 days and could not be \
+                                    deleted.",
+                                   too_old.len()));
+    }
+    check_msg(context.say(&response));
+});
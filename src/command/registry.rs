@@ -0,0 +1,189 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Describes every command: its name, category, description and usage. Both
+//! the descriptions given to the framework and the `help` command are built
+//! from this, so that they never disagree.
+
+/// The help for a single command.
+#[derive(Clone, Copy, Debug)]
+pub struct CommandHelp {
+    pub name: &'static str,
+    pub category: &'static str,
+    pub description: &'static str,
+    /// The arguments the command takes, e.g. `<question>`.
+    pub usage: &'static str,
+}
+
+/// Returns the help of every command enabled at compile time, ordered by name.
+pub fn commands() -> Vec<CommandHelp> {
+    let mut commands = Vec::new();
+    macro_rules! help {
+        ($name:expr, $category:expr, $description:expr, $usage:expr) => {
+            commands.push(CommandHelp {
+                name: $name,
+                category: $category,
+                description: $description,
+                usage: $usage,
+            });
+        };
+    }
+
+    #[cfg(feature = "about")]
+    help!("about", "Information", "Responds with information about the bot.", "");
+    #[cfg(feature = "avatar")]
+    help!("avatar",
+          "Information",
+          "Responds with the avatar of the mentioned user, or your own.",
+          "[@user]");
+    #[cfg(feature = "ban")]
+    help!("ban",
+          "Moderation",
+          "Bans the mentioned user from this server, optionally deleting their messages from \
+           the last 0-7 days.",
+          "<@user> [days]");
+    #[cfg(feature = "botchannel")]
+    help!("botchannel",
+          "Server Settings",
+          "Confines commands in this server to certain channels.",
+          "[add|remove] [#channel]");
+    #[cfg(feature = "choose")]
+    help!("choose",
+          "Fun",
+          "Picks one of several options, separated by `|`, at random.",
+          "<option> | <option> [| ...]");
+    #[cfg(feature = "toggle")]
+    help!("command",
+          "Server Settings",
+          "Disables or enables a command in this server.",
+          "[enable|disable <command>]");
+    #[cfg(feature = "convert")]
+    help!("convert",
+          "Utility",
+          "Converts an amount between units or currencies.",
+          "<amount> <from> <to>");
+    #[cfg(feature = "debug")]
+    help!("debug", "Owner", "Responds with the bot's runtime state.", "");
+    #[cfg(feature = "eightball")]
+    help!("8ball", "Fun", "Answers a question like a Magic 8-Ball.", "<question>");
+    #[cfg(feature = "flip")]
+    help!("flip", "Fun", "Flips one or more coins.", "[count]");
+    #[cfg(feature = "fuyu")]
+    help!("fuyu",
+          "Fun",
+          "Generates a message from a chat log.",
+          "[chat log] [starting word] | reload");
+    #[cfg(feature = "help")]
+    help!("help",
+          "Information",
+          "Lists the available commands, or describes one of them.",
+          "[command]");
+    #[cfg(feature = "kick")]
+    help!("kick", "Moderation", "Kicks the mentioned member from this server.", "<@user>");
+    #[cfg(feature = "ping")]
+    help!("ping",
+          "Information",
+          "Responds with 'Pong', as well as a latency estimate averaged over up to 5 \
+           samples.",
+          "[samples]");
+    #[cfg(feature = "poll")]
+    help!("poll",
+          "Utility",
+          "Posts a poll which users can vote on via reactions, or tallies its votes.",
+          "\"<question>\" \"<option>\" \"<option>\" [...] | results <message id>");
+    #[cfg(feature = "prefix")]
+    help!("prefix",
+          "Server Settings",
+          "Shows, sets or resets the command prefix for this server.",
+          "[set <prefix>|reset]");
+    #[cfg(feature = "purge")]
+    help!("purge", "Moderation", "Deletes the most recent messages in this channel.", "<count>");
+    #[cfg(feature = "reload")]
+    help!("reload", "Owner", "Reloads the bot's config from disk.", "");
+    #[cfg(feature = "remind")]
+    help!("remindme",
+          "Utility",
+          "Reminds you of something after the specified amount of time, e.g. `1h30m`.",
+          "<time> <message>");
+    #[cfg(feature = "role")]
+    help!("role",
+          "Utility",
+          "Gives you, or removes, one of this server's self-assignable roles, which admins \
+           choose with allow and disallow.",
+          "[add|remove|allow|disallow <role>]");
+    #[cfg(feature = "roll")]
+    help!("roll",
+          "Fun",
+          "Rolls dice, e.g. `2d6+3`, or saves, lists and deletes roll macros.",
+          "<dice> [...] | save <name> <dice> | list | delete <name>");
+    #[cfg(feature = "say")]
+    help!("say",
+          "Owner",
+          "Posts a message to a channel as the bot.",
+          "<#channel> [--everyone] <text>");
+    #[cfg(feature = "serverinfo")]
+    help!("serverinfo", "Information", "Responds with information about this server.", "");
+    #[cfg(feature = "shutdown")]
+    help!("shutdown", "Owner", "Saves any unsaved data and shuts the bot down.", "");
+    #[cfg(feature = "stats")]
+    help!("stats",
+          "Information",
+          "Responds with the bot's command statistics, its system information, or the \
+           commands a user runs the most.",
+          "[system|user <@user>|reset]");
+    #[cfg(feature = "tag")]
+    help!("tag",
+          "Utility",
+          "Posts, creates and manages snippets of text.",
+          "<name> | get <name> | create <name> [--category <category>] <content> | \
+           edit <name> <content> | info|raw|delete|claim <name> | \
+           rename|alias <name> <new name> | transfer <name> <@user> | list [category] [page] | \
+           categories | search <query> | random | top | export | import [skip|overwrite]");
+    #[cfg(feature = "uptime")]
+    help!("uptime", "Information", "Responds with how long the bot has been running.", "");
+    #[cfg(feature = "urban")]
+    help!("urban",
+          "Fun",
+          "Responds with the Urban Dictionary definition of a term.",
+          "<term>");
+    #[cfg(feature = "userinfo")]
+    help!("userinfo",
+          "Information",
+          "Responds with information about the mentioned user, or yourself.",
+          "[@user]");
+    #[cfg(feature = "weather")]
+    help!("weather",
+          "Information",
+          "Responds with the current weather at a location.",
+          "<location>");
+    #[cfg(feature = "welcome")]
+    help!("welcome",
+          "Server Settings",
+          "Sets or disables the message with which this server welcomes new members. `%user%` \
+           and `%guild%` are replaced with the new member and the server's name.",
+          "[set [#channel] <message>|disable]");
+    #[cfg(feature = "wolfram")]
+    help!("wolfram",
+          "Information",
+          "Responds with the Wolfram|Alpha answer to a query.",
+          "<query>");
+    #[cfg(feature = "xkcd")]
+    help!("xkcd",
+          "Fun",
+          "Responds with the latest, a random, a numbered or a matching XKCD comic.",
+          "[random|<number>|search <query>]");
+
+    commands.sort_by(|a, b| a.name.cmp(b.name));
+    commands
+}
+
+/// Returns the help of the command with the specified name, if it is enabled.
+pub fn find(name: &str) -> Option<CommandHelp> {
+    commands().into_iter().find(|command| command.name == name)
+}
@@ -0,0 +1,28 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `reload` command, which re-reads the bot's
+//! config from disk without requiring a restart.
+
+use ::{CONFIG, CONFIG_PATH};
+use config::Config;
+use util::{check_msg, is_owner};
+
+command!(reload(context, message, _args) {
+    if !is_owner(message.author.id) {
+        return Err("You do not have permission to do that.".to_owned());
+    }
+
+    let config = Config::load_from_file(&CONFIG_PATH)
+        .map_err(|err| format!("Failed to reload config: {}", err))?;
+    *CONFIG.write().expect("Failed to lock config") = config;
+    info!("Reloaded config from: \"{}\"", *CONFIG_PATH);
+
+    check_msg(context.say("Config reloaded."));
+});
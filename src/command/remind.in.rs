@@ -0,0 +1,9 @@
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct Reminder {
+    /// The user to remind.
+    user_id: u64,
+    /// The channel in which the reminder was requested, and will be delivered.
+    channel_id: u64,
+    message: String,
+    due_at: DateTime<UTC>,
+}
@@ -0,0 +1,195 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `remindme` command, which reminds the user
+//! of something after the specified amount of time, e.g.
+//! `!remindme 1h30m take out the trash`.
+//!
+//! Reminders are saved to disk, so they survive restarts; any which came due
+//! while the bot was offline are delivered as soon as it starts again.
+
+use chrono::{DateTime, Duration, UTC};
+use error::{CommandError, CommandResult, Result as SmexybotResult};
+use serenity::model::ChannelId;
+use std::thread;
+use std::time::Duration as StdDuration;
+use util::{Delivery, JsonStore, check_msg, check_msg_retry, data_path, duration_to_string};
+
+const REMINDERS_FILE: &'static str = "reminders.json";
+
+const USAGE: &'static str = "Please specify when to remind you and what of (e.g. remindme 1h30m \
+                             take out the trash)";
+
+/// The maximum number of pending reminders a single user may have.
+const MAX_REMINDERS: usize = 25;
+
+/// The maximum number of days in the future a reminder may be set for.
+const MAX_DAYS: i64 = 365;
+
+/// The number of seconds between checks for due reminders.
+const POLL_INTERVAL: u64 = 5;
+
+/// The number of times to try delivering a reminder before leaving it for the
+/// next check.
+const SEND_ATTEMPTS: u32 = 3;
+
+lazy_static! {
+    static ref REMINDERS: Reminders = Reminders::new(&data_path(REMINDERS_FILE));
+}
+
+#[cfg(feature = "nightly")]
+include!("remind.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/remind.rs"));
+
+command!(remindme(context, message, args) {
+    let mut args = args.into_iter();
+    let duration = match args.next() {
+        Some(duration) => parse_duration(&duration)?,
+        None => return Err(USAGE.to_owned()),
+    };
+    let text = args.collect::<Vec<String>>().join(" ");
+    if text.trim().is_empty() {
+        return Err(USAGE.to_owned());
+    }
+
+    REMINDERS.insert(Reminder {
+            user_id: message.author.id.0,
+            channel_id: message.channel_id.0,
+            message: text,
+            due_at: UTC::now() + duration,
+        })?;
+
+    check_msg(context.say(&format!("Okay, I'll remind you in {}.",
+                                   duration_to_string(&duration))));
+});
+
+/// Starts the background thread which delivers reminders once they are due.
+pub fn start() {
+    thread::spawn(|| {
+        loop {
+            for reminder in REMINDERS.due(UTC::now()) {
+                let content = format!("<@{}>, you asked me to remind you: {}",
+                                      reminder.user_id,
+                                      reminder.message);
+                let channel_id = ChannelId(reminder.channel_id);
+                // A reminder which failed to send is kept, so that it is
+                // tried again at the next check, unless it never can be.
+                match check_msg_retry(|| channel_id.say(&content), SEND_ATTEMPTS) {
+                    Delivery::Sent => REMINDERS.remove(&reminder),
+                    Delivery::Failed => {},
+                    Delivery::Rejected => {
+                        warn!("Dropping undeliverable reminder for {}", reminder.user_id);
+                        REMINDERS.remove(&reminder);
+                    },
+                }
+            }
+
+            thread::sleep(StdDuration::from_secs(POLL_INTERVAL));
+        }
+    });
+}
+
+#[derive(Debug)]
+struct Reminders {
+    reminders: JsonStore<Vec<Reminder>>,
+}
+
+impl Reminders {
+    fn new(name: &str) -> Self {
+        Reminders { reminders: JsonStore::load(name) }
+    }
+
+    /// Adds the reminder, then persists the change.
+    fn insert(&self, reminder: Reminder) -> CommandResult<()> {
+        self.reminders.modify(|reminders| {
+            let pending = reminders.iter().filter(|r| r.user_id == reminder.user_id).count();
+            if pending >= MAX_REMINDERS {
+                return Err(format!("You cannot have more than {} pending reminders",
+                                   MAX_REMINDERS)
+                    .into());
+            }
+
+            reminders.push(reminder);
+            Ok(())
+        })
+    }
+
+    /// Returns every reminder which is due at the specified time. They are
+    /// only removed once delivered, so that none are lost if sending fails.
+    fn due(&self, now: DateTime<UTC>) -> Vec<Reminder> {
+        self.reminders.read(|reminders| {
+            reminders.iter().filter(|reminder| reminder.due_at <= now).cloned().collect()
+        })
+    }
+
+    /// Removes the reminder, e.g. once it has been delivered, then persists
+    /// the change.
+    fn remove(&self, reminder: &Reminder) {
+        let result: SmexybotResult<()> = self.reminders.modify(|reminders| {
+            if let Some(position) = reminders.iter().position(|pending| pending == reminder) {
+                reminders.remove(position);
+            }
+            Ok(())
+        });
+
+        if let Err(err) = result {
+            warn!("Failed to save reminders: {}", err);
+        }
+    }
+}
+
+// Parses a duration made up of one or more amounts with units, e.g. `10m`,
+// `2h` or `1d12h`. The supported units are weeks, days, hours, minutes and
+// seconds.
+fn parse_duration(input: &str) -> CommandResult<Duration> {
+    let mut total = Duration::zero();
+    let mut amount = String::new();
+    for c in input.chars() {
+        if c.is_digit(10) {
+            amount.push(c);
+            continue;
+        }
+
+        let amount = match amount.drain(..).collect::<String>().parse::<i64>() {
+            Ok(amount) => amount,
+            Err(_) => return Err(USAGE.into()),
+        };
+        // Checked up front as well, as a huge amount would overflow the
+        // `Duration`.
+        if amount > MAX_DAYS * 24 * 60 * 60 {
+            return Err(too_long());
+        }
+
+        let duration = match c {
+            'w' => Duration::weeks(amount),
+            'd' => Duration::days(amount),
+            'h' => Duration::hours(amount),
+            'm' => Duration::minutes(amount),
+            's' => Duration::seconds(amount),
+            _ => return Err(USAGE.into()),
+        };
+        total = total + duration;
+    }
+
+    // Every amount must be followed by its unit.
+    if !amount.is_empty() || total <= Duration::zero() {
+        return Err(USAGE.into());
+    }
+    if total > Duration::days(MAX_DAYS) {
+        return Err(too_long());
+    }
+
+    Ok(total)
+}
+
+fn too_long() -> CommandError {
+    format!("Reminders cannot be set for more than {} days", MAX_DAYS).into()
+}
@@ -0,0 +1,189 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `role` command, which allows users to give
+//! themselves, or remove, the roles which a guild's admins have made
+//! self-assignable, e.g. `!role add Notifications`.
+
+use error::Result as SmexybotResult;
+use hyper::status::StatusCode;
+use serenity::Error as SerenityError;
+use serenity::client::{CACHE, ClientError, rest};
+use serenity::model::{GuildId, Message, RoleId, permissions};
+use std::collections::{HashMap, HashSet};
+use util::{JsonStore, bot_has_permissions, check_msg, data_path, is_guild_admin};
+
+const SELF_ROLES_FILE: &'static str = "self_roles.json";
+
+lazy_static! {
+    static ref SELF_ROLES: SelfRoles = SelfRoles::new(&data_path(SELF_ROLES_FILE));
+}
+
+command!(role(context, message, args) {
+    let guild_id = match message.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Err("Roles can only be assigned in a server.".to_owned()),
+    };
+
+    let subcommand = args.first().cloned();
+    let name = args.iter().skip(1).cloned().collect::<Vec<String>>().join(" ");
+    let subcommand = match subcommand.as_ref().map(String::as_ref) {
+        Some(subcommand @ "add") |
+        Some(subcommand @ "remove") |
+        Some(subcommand @ "allow") |
+        Some(subcommand @ "disallow") => subcommand,
+        _ => {
+            let roles = SELF_ROLES.list(guild_id)
+                .into_iter()
+                .filter_map(|role_id| role_name(guild_id, role_id))
+                .collect::<Vec<String>>();
+            check_msg(context.say(&if roles.is_empty() {
+                "This server has no self-assignable roles.".to_owned()
+            } else {
+                format!("Self-assignable roles: {}", roles.join(", "))
+            }));
+            return Ok(());
+        },
+    };
+
+    if name.trim().is_empty() {
+        return Err("Please specify the name of a role.".to_owned());
+    }
+    let role_id = match find_role(guild_id, &name) {
+        Some(role_id) => role_id,
+        None => return Err(format!("No role named \"{}\" exists.", name)),
+    };
+
+    match subcommand {
+        "allow" | "disallow" => {
+            if !is_guild_admin(guild_id, message.author.id) {
+                return Err("You do not have permission to do that.".to_owned());
+            }
+
+            let allowed = subcommand == "allow";
+            let changed = SELF_ROLES.set(guild_id, role_id, allowed)
+                .map_err(|err| format!("Failed to save self-assignable roles: {}", err))?;
+            check_msg(context.say(&match (allowed, changed) {
+                (true, true) => format!("\"{}\" is now self-assignable.", name),
+                (true, false) => format!("\"{}\" is already self-assignable.", name),
+                (false, true) => format!("\"{}\" is no longer self-assignable.", name),
+                (false, false) => format!("\"{}\" is not self-assignable.", name),
+            }));
+        },
+        _ => {
+            if !SELF_ROLES.is_allowed(guild_id, role_id) {
+                return Err(format!("\"{}\" is not self-assignable.", name));
+            }
+            if !bot_has_permissions(guild_id, permissions::MANAGE_ROLES) {
+                return Err("I need the Manage Roles permission to do that.".to_owned());
+            }
+
+            let add = subcommand == "add";
+            assign(guild_id, message, role_id, add)?;
+            check_msg(context.say(&if add {
+                format!("You now have the \"{}\" role.", name)
+            } else {
+                format!("You no longer have the \"{}\" role.", name)
+            }));
+        },
+    }
+});
+
+// Gives the role to, or removes it from, the author of the message.
+fn assign(guild_id: GuildId, message: &Message, role_id: RoleId, add: bool) -> Result<(), String> {
+    let result = if add {
+        rest::add_member_role(guild_id.0, message.author.id.0, role_id.0)
+    } else {
+        rest::remove_member_role(guild_id.0, message.author.id.0, role_id.0)
+    };
+
+    match result {
+        Ok(()) => Ok(()),
+        // Discord refuses to manage roles at or above the bot's highest role.
+        Err(SerenityError::Client(ClientError::InvalidRequest(StatusCode::Forbidden))) => {
+            Err("I cannot manage that role, as it is not below my highest role.".to_owned())
+        },
+        Err(err) => Err(format!("Failed to update your roles: {:?}", err)),
+    }
+}
+
+// Returns the ID of the guild's role with the name, ignoring case.
+fn find_role(guild_id: GuildId, name: &str) -> Option<RoleId> {
+    let name = name.trim().to_lowercase();
+    let cache = match CACHE.read() {
+        Ok(cache) => cache,
+        Err(_) => return None,
+    };
+    cache.guilds
+        .get(&guild_id)
+        .and_then(|guild| guild.roles.values().find(|role| role.name.to_lowercase() == name))
+        .map(|role| role.id)
+}
+
+fn role_name(guild_id: GuildId, role_id: RoleId) -> Option<String> {
+    let cache = match CACHE.read() {
+        Ok(cache) => cache,
+        Err(_) => return None,
+    };
+    cache.guilds
+        .get(&guild_id)
+        .and_then(|guild| guild.roles.get(&role_id))
+        .map(|role| role.name.clone())
+}
+
+/// The roles of every guild which its members may assign themselves, keyed by
+/// guild ID.
+#[derive(Debug)]
+struct SelfRoles {
+    roles: JsonStore<HashMap<String, HashSet<u64>>>,
+}
+
+impl SelfRoles {
+    fn new(name: &str) -> Self {
+        SelfRoles { roles: JsonStore::load(name) }
+    }
+
+    /// Returns whether members of the guild may assign themselves the role.
+    fn is_allowed(&self, guild_id: GuildId, role_id: RoleId) -> bool {
+        self.roles.read(|roles| {
+            roles.get(&guild_id.to_string()).map_or(false, |roles| roles.contains(&role_id.0))
+        })
+    }
+
+    /// Returns the guild's self-assignable roles.
+    fn list(&self, guild_id: GuildId) -> Vec<RoleId> {
+        let mut roles = self.roles.read(|roles| {
+            roles.get(&guild_id.to_string())
+                .map(|roles| roles.iter().cloned().collect::<Vec<u64>>())
+                .unwrap_or_else(Vec::new)
+        });
+        roles.sort();
+        roles.into_iter().map(RoleId).collect()
+    }
+
+    /// Makes the role self-assignable, or not, then persists the change.
+    /// Returns whether the guild's self-assignable roles changed.
+    fn set(&self, guild_id: GuildId, role_id: RoleId, allowed: bool) -> SmexybotResult<bool> {
+        self.roles.modify(|roles| {
+            let key = guild_id.to_string();
+            if allowed {
+                return Ok(roles.entry(key).or_insert_with(HashSet::new).insert(role_id.0));
+            }
+
+            let (changed, now_empty) = match roles.get_mut(&key) {
+                Some(guild_roles) => (guild_roles.remove(&role_id.0), guild_roles.is_empty()),
+                None => (false, false),
+            };
+            if now_empty {
+                roles.remove(&key);
+            }
+            Ok(changed)
+        })
+    }
+}
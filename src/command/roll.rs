@@ -8,106 +8,635 @@
 // except according to those terms.
 
 //! Provides functionality for the `!roll` command.
+//!
+//! Users may also save rolls they make often as named macros, e.g.
+//! `!roll save atk 1d20+5`, and then roll them with `!roll atk`.
 
 extern crate regex;
 
+use ::current_config;
 use rand::{self, Rng};
+use error::{CommandError, CommandResult};
 use self::regex::Regex;
+use serenity::client::Context;
+use serenity::model::{Message, UserId};
+use std::collections::HashMap;
+use std::fmt;
+use std::u32;
 
-use util::check_msg;
+use util::{JsonStore, check_msg, data_path, paginate, truncate_words};
+
+const MACROS_FILE: &'static str = "roll_macros.json";
+
+/// The maximum number of macros a single user may save.
+const MAX_MACROS: usize = 25;
+
+/// The maximum length of a Discord message.
+const MAX_MESSAGE_LENGTH: usize = 2000;
+/// The maximum number of messages the results of a single roll are split
+/// across.
+const MAX_MESSAGES: usize = 3;
 
 lazy_static! {
-    static ref DICE_ROLL_REGEX: Regex = Regex::new(r"^(\d*)d(\d*)").unwrap();
+    static ref DICE_ROLL_REGEX: Regex =
+        Regex::new(r"^(\d*)d(\d*|F|%)(!)?(?:k([hl])(\d+))?([+-]\d+)?$").unwrap();
+    static ref MACROS: Macros = Macros::new(&data_path(MACROS_FILE));
 }
 
-command!(roll(context, _message, args) {
-    const ERROR_MESSAGE: &'static str = "Please specify a roll in the form XdY (e.g. 2d6)";
-
+command!(roll(context, message, args) {
     trace!("Received roll command with args: {:?}", args);
-    let arg = match args.iter().next() {
-        Some(arg) => arg,
-        None => {
-            check_msg(context.say(ERROR_MESSAGE));
-            return Ok(());
-        },
+    match args.first().map(String::as_ref) {
+        Some("save") => return save_macro(context, message, &args[1..]).map_err(From::from),
+        Some("list") => return list_macros(context, message).map_err(From::from),
+        Some("delete") => return delete_macro(context, message, &args[1..]).map_err(From::from),
+        Some(_) => {},
+        None => return Err(ERROR_MESSAGE.to_owned()),
+    }
+
+    let args = expand_macros(message.author.id, args);
+    let dice_rolls = parse_rolls(&args)?;
+
+    let mut rng = rand::thread_rng();
+    let mut results = Vec::new();
+    let mut grand_total = 0i64;
+    for dice_roll in &dice_rolls {
+        let result = dice_roll.roll(&mut rng)?;
+        grand_total += result.total;
+        results.push(result);
+    }
+
+    if results.len() == 1 {
+        // A summary on its own would not say what was rolled. In verbose mode,
+        // what was rolled is always shown, as it is for several groups.
+        let response = if results[0].is_collapsed() || current_config().verbose_rolls {
+            format!("{}: {}", args[0], results[0])
+        } else {
+            results[0].to_string()
+        };
+        check_msg(context.say(&truncate_words(&response, MAX_MESSAGE_LENGTH)));
+        return Ok(());
+    }
+
+    for page in &group_pages(&args, &results, grand_total) {
+        check_msg(context.say(page));
+    }
+});
+
+// Splits the results of several groups across messages, as many groups may
+// not fit in a single one. If even that would take too many messages, only the
+// total of each group is shown, and failing that, only the grand total.
+fn group_pages(args: &[String], results: &[RollResult], grand_total: i64) -> Vec<String> {
+    let total = format!("Total: {}", grand_total);
+    let mut lines = args.iter()
+        .zip(results.iter())
+        .map(|(arg, result)| format!("{}: {}", arg, result))
+        .chain(Some(total.clone()))
+        .map(|line| truncate_words(&line, MAX_MESSAGE_LENGTH))
+        .collect::<Vec<String>>();
+    let mut pages = paginate(&lines, "\n", MAX_MESSAGE_LENGTH);
+    if pages.len() > MAX_MESSAGES {
+        lines = args.iter()
+            .zip(results.iter())
+            .map(|(arg, result)| format!("{}: {}", arg, result.total))
+            .chain(Some(total.clone()))
+            .map(|line| truncate_words(&line, MAX_MESSAGE_LENGTH))
+            .collect();
+        pages = paginate(&lines, "\n", MAX_MESSAGE_LENGTH);
+    }
+    if pages.len() > MAX_MESSAGES {
+        pages = vec![format!("Rolled {} groups. {}", results.len(), total)];
+    }
+
+    pages
+}
+
+const ERROR_MESSAGE: &'static str = "Please specify a roll in the form [X]dY[!][khN|klN][+Z] \
+                                      (e.g. 2d6+3, d20, 4d6kh3, 3d6!, 4dF or d%)";
+
+/// The saved roll macros of every user, keyed by user ID and then by macro name.
+#[derive(Debug)]
+struct Macros {
+    macros: JsonStore<HashMap<String, HashMap<String, String>>>,
+}
+
+impl Macros {
+    fn new(name: &str) -> Self {
+        Macros { macros: JsonStore::load(name) }
+    }
+
+    /// Returns the expression saved under the user's macro of that name.
+    fn get(&self, user_id: UserId, name: &str) -> Option<String> {
+        self.macros.read(|macros| {
+            macros.get(&user_id.to_string()).and_then(|macros| macros.get(name)).cloned()
+        })
+    }
+
+    /// Returns all of the user's macros, ordered by name.
+    fn list(&self, user_id: UserId) -> Vec<(String, String)> {
+        let mut macros = self.macros.read(|macros| {
+            macros.get(&user_id.to_string())
+                .cloned()
+                .unwrap_or_else(HashMap::new)
+                .into_iter()
+                .collect::<Vec<(String, String)>>()
+        });
+        macros.sort();
+        macros
+    }
+
+    /// Saves the user's macro, replacing any existing macro of the same name,
+    /// then persists the change.
+    fn insert(&self, user_id: UserId, name: String, expression: String) -> CommandResult<()> {
+        self.macros.modify(|macros| {
+            let user_macros = macros.entry(user_id.to_string()).or_insert_with(HashMap::new);
+            if !user_macros.contains_key(&name) && user_macros.len() >= MAX_MACROS {
+                return Err(format!("You cannot save more than {} macros", MAX_MACROS).into());
+            }
+            user_macros.insert(name, expression);
+            Ok(())
+        })
+    }
+
+    /// Deletes the user's macro, then persists the change.
+    fn remove(&self, user_id: UserId, name: &str) -> CommandResult<()> {
+        self.macros.modify(|macros| {
+            let key = user_id.to_string();
+            let (removed, now_empty) = match macros.get_mut(&key) {
+                Some(user_macros) => (user_macros.remove(name).is_some(), user_macros.is_empty()),
+                None => (false, false),
+            };
+            if !removed {
+                return Err(CommandError::NotFound(format!("No macro named \"{}\"", name)));
+            }
+            if now_empty {
+                macros.remove(&key);
+            }
+            Ok(())
+        })
+    }
+}
+
+fn save_macro(context: &Context, message: &Message, args: &[String]) -> CommandResult<()> {
+    let (name, expression) = match args.split_first() {
+        Some((name, expression)) if !expression.is_empty() => (name.to_lowercase(), expression),
+        _ => return Err("Please specify a name for the macro and the roll to save.".into()),
+    };
+
+    // A macro named like a subcommand or a dice expression could never be
+    // rolled, as those always take precedence.
+    if name == "save" || name == "list" || name == "delete" || DICE_ROLL_REGEX.is_match(&name) {
+        return Err(format!("\"{}\" cannot be used as a macro name", name).into());
+    }
+    if name.chars().count() > 32 {
+        return Err("Macro names are limited to 32 characters".into());
+    }
+
+    parse_rolls(expression)?;
+    let expression = expression.join(" ");
+    MACROS.insert(message.author.id, name.clone(), expression.clone())?;
+    check_msg(context.say(&format!("Macro \"{}\" saved as `{}`.", name, expression)));
+
+    Ok(())
+}
+
+fn list_macros(context: &Context, message: &Message) -> CommandResult<()> {
+    let macros = MACROS.list(message.author.id);
+    if macros.is_empty() {
+        check_msg(context.say("You have no saved macros."));
+        return Ok(());
+    }
+
+    let lines = macros.into_iter()
+        .map(|(name, expression)| format!("{}: `{}`", name, expression))
+        .collect::<Vec<String>>();
+    check_msg(context.say(&lines.join("\n")));
+
+    Ok(())
+}
+
+fn delete_macro(context: &Context, message: &Message, args: &[String]) -> CommandResult<()> {
+    let name = match args.first() {
+        Some(name) => name.to_lowercase(),
+        None => return Err("Please specify a macro to delete.".into()),
     };
 
-    let mut captures = DICE_ROLL_REGEX.captures_iter(arg);
-    let next_capture = captures.next();
-    let (number_of_dice, die_sides) = match next_capture {
-        Some(capture) => {
-            let number_of_dice = match capture.at(1) {
-                Some(number_of_dice) => {
-                    match number_of_dice.parse::<u32>() {
-                        Ok(number_of_dice) => number_of_dice,
-                        _ => {
-                            check_msg(context.say(ERROR_MESSAGE));
-                            return Ok(());
-                        },
+    MACROS.remove(message.author.id, &name)?;
+    check_msg(context.say(&format!("Macro \"{}\" deleted.", name)));
+
+    Ok(())
+}
+
+// Replaces each argument which names one of the user's macros with the groups
+// of the saved expression. Anything which is already a dice expression is left
+// as-is, even if a macro of the same name exists.
+fn expand_macros(user_id: UserId, args: Vec<String>) -> Vec<String> {
+    let mut expanded = Vec::new();
+    for arg in args {
+        if DICE_ROLL_REGEX.is_match(&arg) {
+            expanded.push(arg);
+            continue;
+        }
+
+        match MACROS.get(user_id, &arg.to_lowercase()) {
+            Some(expression) => expanded.extend(expression.split_whitespace().map(String::from)),
+            None => expanded.push(arg),
+        }
+    }
+    expanded
+}
+
+// Parses every group up front, so that no dice are rolled if any of the groups
+// is invalid.
+fn parse_rolls(args: &[String]) -> CommandResult<Vec<DiceRoll>> {
+    let mut dice_rolls = Vec::new();
+    for arg in args {
+        match parse_roll(arg) {
+            Ok(dice_roll) => dice_rolls.push(dice_roll),
+            Err(err) if args.len() == 1 => return Err(err),
+            Err(err) => return Err(format!("Invalid roll group \"{}\": {}", arg, err).into()),
+        }
+    }
+
+    Ok(dice_rolls)
+}
+
+/// The maximum number of times a single exploding die may be rerolled.
+const MAX_EXPLOSIONS: usize = 100;
+
+/// The number of dice above which a roll is summarized rather than listing
+/// every die.
+const COLLAPSE_THRESHOLD: usize = 20;
+
+/// A single parsed dice expression, e.g. `2d6+3`.
+#[derive(Clone, Copy, Debug)]
+struct DiceRoll {
+    number_of_dice: u32,
+    die_sides: u32,
+    /// Whether the dice are Fudge dice, with faces of -1, 0 and +1.
+    fudge: bool,
+    /// Whether a die showing its maximum value is rerolled and added.
+    explode: bool,
+    keep: Option<Keep>,
+    modifier: i32,
+}
+
+/// Which dice of a roll count towards its sum.
+#[derive(Clone, Copy, Debug)]
+enum Keep {
+    /// Keep the N highest dice.
+    Highest(u32),
+    /// Keep the N lowest dice.
+    Lowest(u32),
+}
+
+impl DiceRoll {
+    /// Rolls the dice using the given random number generator, returning the
+    /// individual rolls and the total.
+    ///
+    /// Taking the generator as an argument allows a seeded one to be used, so
+    /// that the outcome of a roll can be reproduced.
+    fn roll<R: Rng>(&self, rng: &mut R) -> CommandResult<RollResult> {
+        let mut rolls = (0..self.number_of_dice)
+            .map(|_| {
+                if self.fudge {
+                    let value = rng.gen_range::<i64>(-1, 2);
+                    return Die {
+                        value: value,
+                        chain: vec![value],
+                        dropped: false,
+                        fudge: true,
+                    };
+                }
+
+                let max = i64::from(self.die_sides);
+                let mut chain = vec![rng.gen_range::<i64>(1, max + 1)];
+                if self.explode {
+                    while chain.last() == Some(&max) && chain.len() <= MAX_EXPLOSIONS {
+                        chain.push(rng.gen_range::<i64>(1, max + 1));
                     }
+                }
+
+                Die {
+                    value: chain.iter().sum(),
+                    chain: chain,
+                    dropped: false,
+                    fudge: false,
+                }
+            })
+            .collect::<Vec<Die>>();
+
+        if let Some(keep) = self.keep {
+            // Order the dice from the most to the least desirable, then drop
+            // everything past the number of dice to keep.
+            let mut order = (0..rolls.len()).collect::<Vec<usize>>();
+            let kept = match keep {
+                Keep::Highest(kept) => {
+                    order.sort_by(|a, b| rolls[*b].value.cmp(&rolls[*a].value));
+                    kept
                 },
-                _ => {
-                    check_msg(context.say(ERROR_MESSAGE));
-                    return Ok(());
+                Keep::Lowest(kept) => {
+                    order.sort_by(|a, b| rolls[*a].value.cmp(&rolls[*b].value));
+                    kept
                 },
             };
+            for index in order.into_iter().skip(kept as usize) {
+                rolls[index].dropped = true;
+            }
+        }
 
-            let die_sides = match capture.at(2) {
-                Some(die_sides) => {
-                    match die_sides.parse::<u32>() {
-                        Ok(0) => {
-                            check_msg(context.say("Number of die sides cannot be 0."));
-                            return Ok(());
-                        },
-                        Ok(4294967295) => {
-                            check_msg(context.say("Number of die sides is too large"));
-                            return Ok(());
-                        },
-                        Ok(die_sides) => die_sides,
-                        _ => {
-                            check_msg(context.say(ERROR_MESSAGE));
-                            return Ok(());
-                        },
-                    }
-                },
-                _ => {
-                    check_msg(context.say(ERROR_MESSAGE));
-                    return Ok(());
-                },
+        let mut sum = 0i64;
+        for die in rolls.iter().filter(|die| !die.dropped) {
+            sum = sum.checked_add(die.value)
+                .ok_or("Unable to calculate result: sum of rolls too large")?;
+        }
+
+        Ok(RollResult {
+            rolls: rolls,
+            modifier: self.modifier,
+            total: sum + i64::from(self.modifier),
+            fudge: self.fudge,
+        })
+    }
+}
+
+/// A single rolled die.
+#[derive(Clone, Debug)]
+struct Die {
+    value: i64,
+    /// The individual results making up the die, if it exploded.
+    chain: Vec<i64>,
+    /// Whether the die was discarded by a keep-highest/keep-lowest rule.
+    dropped: bool,
+    /// Whether the die is a Fudge die, rendered as `+`, `0` or `-`.
+    fudge: bool,
+}
+
+impl fmt::Display for Die {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = if self.fudge {
+            match self.value {
+                1 => "+".to_owned(),
+                -1 => "-".to_owned(),
+                _ => "0".to_owned(),
+            }
+        } else if self.chain.len() > 1 {
+            let chain = self.chain
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<String>>()
+                .join("!");
+            format!("{} ({})", self.value, chain)
+        } else {
+            self.value.to_string()
+        };
+
+        if self.dropped {
+            write!(f, "~~{}~~", value)
+        } else {
+            write!(f, "{}", value)
+        }
+    }
+}
+
+/// The outcome of rolling a `DiceRoll`.
+#[derive(Debug)]
+struct RollResult {
+    rolls: Vec<Die>,
+    modifier: i32,
+    total: i64,
+    fudge: bool,
+}
+
+impl RollResult {
+    /// Whether there are too many dice to list each of them individually.
+    fn is_collapsed(&self) -> bool {
+        self.rolls.len() > COLLAPSE_THRESHOLD
+    }
+}
+
+impl fmt::Display for RollResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // A lone die is shown as just its value, unless it exploded, as the
+        // chain would otherwise be hidden, or it is a Fudge die, which is shown
+        // as a symbol.
+        if self.rolls.len() == 1 && self.modifier == 0 && self.rolls[0].chain.len() == 1 &&
+           !self.fudge {
+            return write!(f, "{}", self.total);
+        }
+
+        if self.is_collapsed() {
+            let kept = self.rolls
+                .iter()
+                .filter(|die| !die.dropped)
+                .map(|die| die.value)
+                .collect::<Vec<i64>>();
+            let min = kept.iter().min().cloned().unwrap_or(0);
+            let max = kept.iter().max().cloned().unwrap_or(0);
+            #[cfg_attr(feature = "clippy", allow(cast_precision_loss))]
+            let avg = if kept.is_empty() {
+                0.0
+            } else {
+                kept.iter().sum::<i64>() as f64 / kept.len() as f64
             };
+            return write!(f,
+                          "sum = {} (min {}, max {}, avg {:.2})",
+                          self.total,
+                          min,
+                          max,
+                          avg);
+        }
 
-            assert!(die_sides > 0 && die_sides < 4294967295);
+        // Fudge dice are rendered as symbols, so joining them with `+` would be
+        // ambiguous.
+        let separator = if self.fudge { " " } else { " + " };
+        let mut roll_string = self.rolls
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<String>>()
+            .join(separator);
+        if self.modifier > 0 {
+            roll_string.push_str(&format!(" + {}", self.modifier));
+        } else if self.modifier < 0 {
+            roll_string.push_str(&format!(" - {}", -i64::from(self.modifier)));
+        }
 
-            (number_of_dice, die_sides)
+        write!(f, "{} = {}", roll_string, self.total)
+    }
+}
+
+/// Parses a dice expression of the form `[X]dY[!][khN|klN][+Z]`, where `Y` may
+/// also be `F` for Fudge dice or `%` for percentile dice.
+fn parse_roll(expression: &str) -> CommandResult<DiceRoll> {
+    let capture = match DICE_ROLL_REGEX.captures_iter(expression).next() {
+        Some(capture) => capture,
+        None => return Err(ERROR_MESSAGE.into()),
+    };
+
+    let config = current_config();
+    // A missing number of dice means a single die, e.g. `d20`.
+    let number_of_dice = match capture.at(1) {
+        Some("") => Some(Ok(1)),
+        number_of_dice => number_of_dice.map(str::parse::<u32>),
+    };
+    let number_of_dice = match number_of_dice {
+        Some(Ok(0)) => return Err("Number of dice cannot be 0".into()),
+        Some(Ok(number_of_dice)) if number_of_dice > config.max_dice => {
+            return Err(format!("Number of dice cannot exceed {}", config.max_dice).into());
         },
-        _ => {
-            check_msg(context.say(ERROR_MESSAGE));
-            return Ok(());
+        Some(Ok(number_of_dice)) => number_of_dice,
+        _ => return Err(ERROR_MESSAGE.into()),
+    };
+
+    let fudge = capture.at(2) == Some("F");
+    // Percentile dice, `d%`, are simply d100s.
+    let die_sides = match capture.at(2) {
+        Some("%") => Some(Ok(100)),
+        die_sides => die_sides.map(str::parse::<u32>),
+    };
+    let die_sides = match die_sides {
+        // Fudge dice have three faces: -1, 0 and +1.
+        _ if fudge => 3,
+        // Rolling a die with no sides would leave nothing to roll.
+        Some(Ok(0)) => return Err("A die needs at least one side".into()),
+        Some(Ok(u32::MAX)) => return Err("Number of die sides is too large".into()),
+        Some(Ok(die_sides)) if die_sides > config.max_die_sides => {
+            return Err(format!("Number of die sides cannot exceed {}", config.max_die_sides)
+                .into());
         },
+        Some(Ok(die_sides)) => die_sides,
+        _ => return Err(ERROR_MESSAGE.into()),
     };
 
-    if number_of_dice == 0 {
-        check_msg(context.say("Number of dice cannot be 0"));
-        return Ok(());
-    }
+    // A single-sided die always rolls its maximum, so exploding it would never
+    // terminate.
+    // Fudge dice have no meaningful maximum to explode on either.
+    let explode = capture.at(3).is_some() && die_sides > 1 && !fudge;
 
-    let mut rolls = Vec::new();
-    let mut rng = rand::thread_rng();
-    let mut sum = 0u32;
-    for _ in 1..(number_of_dice + 1) {
-        let roll = rng.gen_range::<u32>(1, die_sides + 1);
-        sum = sum.checked_add(roll).ok_or("Unable to calculate result: sum of rolls too large")?;
-        rolls.push(roll);
-    }
-    let roll_string = rolls.iter()
-        .map(|v| v.to_string())
-        .collect::<Vec<String>>()
-        .join(" + ");
-
-    let response = match rolls.len() {
-        1 => sum.to_string(),
-        _ => format!("{} = {}", roll_string, sum),
+    let keep = match (capture.at(4), capture.at(5).map(str::parse::<u32>)) {
+        (Some(_), Some(Ok(0))) => return Err("Number of dice to keep cannot be 0".into()),
+        (Some(_), Some(Ok(kept))) if kept > number_of_dice => {
+            return Err(format!(
+                    "Cannot keep {} dice when only {} are rolled",
+                    kept,
+                    number_of_dice,
+                )
+                .into());
+        },
+        (Some("h"), Some(Ok(kept))) => Some(Keep::Highest(kept)),
+        (Some("l"), Some(Ok(kept))) => Some(Keep::Lowest(kept)),
+        (None, None) => None,
+        _ => return Err(ERROR_MESSAGE.into()),
     };
 
-    check_msg(context.say(response.as_ref()));
-});
+    let modifier = match capture.at(6).map(str::parse::<i32>) {
+        Some(Ok(modifier)) => modifier,
+        Some(Err(_)) => return Err("Modifier is too large".into()),
+        None => 0,
+    };
+
+    Ok(DiceRoll {
+        number_of_dice: number_of_dice,
+        die_sides: die_sides,
+        fudge: fudge,
+        explode: explode,
+        keep: keep,
+        modifier: modifier,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, StdRng};
+    use super::*;
+
+    const SEED: &'static [usize] = &[1, 2, 3, 4];
+
+    // Rolls the expression with a generator seeded with `SEED`.
+    fn roll_seeded(expression: &str) -> RollResult {
+        let dice_roll = parse_roll(expression).expect("Failed to parse roll");
+        dice_roll.roll(&mut StdRng::from_seed(SEED)).expect("Failed to roll")
+    }
+
+    // Returns the values a generator seeded with `SEED` gives for `count` dice
+    // of `sides` sides, i.e. the dice `roll_seeded` should produce.
+    fn expected_dice(count: usize, sides: i64) -> Vec<i64> {
+        let mut rng = StdRng::from_seed(SEED);
+        (0..count).map(|_| rng.gen_range::<i64>(1, sides + 1)).collect()
+    }
+
+    #[test]
+    fn roll_single_sided_dice() {
+        let result = roll_seeded("3d1+2");
+        assert_eq!(result.total, 5);
+        assert_eq!(result.to_string(), "1 + 1 + 1 + 2 = 5");
+
+        let result = roll_seeded("2d1-3");
+        assert_eq!(result.total, -1);
+        assert_eq!(result.to_string(), "1 + 1 - 3 = -1");
+    }
+
+    #[test]
+    fn roll_seeded_dice() {
+        let dice = expected_dice(2, 6);
+        let result = roll_seeded("2d6+3");
+        let values = result.rolls.iter().map(|die| die.value).collect::<Vec<i64>>();
+        assert_eq!(values, dice);
+        assert_eq!(result.total, dice[0] + dice[1] + 3);
+        assert_eq!(result.to_string(),
+                   format!("{} + {} + 3 = {}", dice[0], dice[1], result.total));
+    }
+
+    #[test]
+    fn roll_seeded_keep_highest() {
+        let dice = expected_dice(4, 6);
+        let lowest = *dice.iter().min().unwrap();
+        let result = roll_seeded("4d6kh3");
+        assert_eq!(result.total, dice.iter().sum::<i64>() - lowest);
+        assert_eq!(result.rolls.iter().filter(|die| die.dropped).count(), 1);
+        let dropped = result.rolls.iter().find(|die| die.dropped).unwrap();
+        assert_eq!(dropped.value, lowest);
+        assert!(result.to_string().contains(&format!("~~{}~~", lowest)));
+    }
+
+    #[test]
+    fn roll_is_reproducible() {
+        let first = roll_seeded("10d20!+4");
+        let second = roll_seeded("10d20!+4");
+        assert_eq!(first.total, second.total);
+        assert_eq!(first.to_string(), second.to_string());
+    }
+
+    #[test]
+    fn parse_roll_without_sides() {
+        let err = parse_roll("2d0").unwrap_err();
+        assert_eq!(err.to_string(), "A die needs at least one side");
+    }
+
+    #[test]
+    fn parse_roll_single_die() {
+        for &(expression, sides) in &[("d20", 20), ("1d20", 20), ("d%", 100)] {
+            let dice_roll = parse_roll(expression).expect("Failed to parse roll");
+            assert_eq!((dice_roll.number_of_dice, dice_roll.die_sides), (1, sides));
+        }
+    }
+
+    #[test]
+    fn group_pages_fit_in_messages() {
+        let mut rng = StdRng::from_seed(SEED);
+        for &groups in &[5, 50, 500] {
+            let args = vec!["20d100!+1".to_owned(); groups];
+            let results = parse_rolls(&args)
+                .expect("Failed to parse rolls")
+                .iter()
+                .map(|dice_roll| dice_roll.roll(&mut rng).expect("Failed to roll"))
+                .collect::<Vec<RollResult>>();
+            let grand_total = results.iter().map(|result| result.total).sum();
+
+            let pages = group_pages(&args, &results, grand_total);
+            assert!(!pages.is_empty() && pages.len() <= MAX_MESSAGES);
+            for page in &pages {
+                assert!(page.chars().count() <= MAX_MESSAGE_LENGTH);
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `say` command, which allows the bot's
+//! owners to post a message as the bot, e.g. for announcements:
+//! `!say #announcements The bot will be down for maintenance tonight.`
+//!
+//! Messages mentioning `@everyone` or `@here` are rejected, unless the
+//! `--everyone` flag is passed before the text.
+
+use serenity::client::CACHE;
+use serenity::model::ChannelId;
+use util::{bot_id, check_msg, is_owner};
+
+/// The flag which allows the message to mention `@everyone` or `@here`.
+const EVERYONE_FLAG: &'static str = "--everyone";
+
+command!(say(context, message, args) {
+    if !is_owner(message.author.id) {
+        return Err("You do not have permission to do that.".to_owned());
+    }
+
+    let mut args = args.into_iter().peekable();
+    let channel_id = match args.next()
+        .map(|channel| channel.trim_left_matches("<#").trim_right_matches('>').parse::<u64>()) {
+        Some(Ok(channel_id)) => ChannelId(channel_id),
+        _ => return Err("Please mention a channel, or give its ID.".to_owned()),
+    };
+
+    let mention_everyone = args.peek().map(String::as_ref) == Some(EVERYONE_FLAG);
+    if mention_everyone {
+        args.next();
+    }
+
+    let text = args.collect::<Vec<String>>().join(" ");
+    if text.trim().is_empty() {
+        return Err("Please specify something to say.".to_owned());
+    }
+    if !mention_everyone && (text.contains("@everyone") || text.contains("@here")) {
+        return Err(format!("The message mentions @everyone or @here; pass {} before it to \
+                            allow this.",
+                           EVERYONE_FLAG));
+    }
+
+    if !can_send(channel_id) {
+        return Err(format!("I cannot send messages in <#{}>.", channel_id.0));
+    }
+
+    channel_id.say(&text)
+        .map_err(|err| format!("Failed to send the message: {:?}", err))?;
+    check_msg(context.say(&format!("Message sent to <#{}>.", channel_id.0)));
+});
+
+// Returns whether the bot may send messages in the channel. Channels outside
+// of any cached guild, e.g. direct messages, are assumed to be sendable.
+fn can_send(channel_id: ChannelId) -> bool {
+    let bot_id = match bot_id() {
+        Some(bot_id) => bot_id,
+        None => return false,
+    };
+    let cache = match CACHE.read() {
+        Ok(cache) => cache,
+        Err(_) => return false,
+    };
+
+    match cache.guilds.values().find(|guild| guild.channels.contains_key(&channel_id)) {
+        Some(guild) => guild.permissions_for(channel_id, bot_id).send_messages(),
+        None => true,
+    }
+}
@@ -0,0 +1,49 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `serverinfo` command.
+
+use serenity::client::CACHE;
+use util::{check_msg, random_colour, snowflake_to_timestamp, time_ago, timestamp_to_string};
+
+command!(serverinfo(context, message, _args) {
+    let guild_id = match message.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Err("This command can only be used in a server.".to_owned()),
+    };
+
+    let cache = match CACHE.read() {
+        Ok(cache) => cache,
+        Err(_) => return Err("Failed to lock cache".to_owned()),
+    };
+    let guild = match cache.guilds.get(&guild_id) {
+        Some(guild) => guild,
+        None => return Err("Failed to retrieve information on this server".to_owned()),
+    };
+
+    let created_at = snowflake_to_timestamp(guild.id.0);
+    let colour = random_colour();
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            let e = e.title(&guild.name)
+                .colour(colour)
+                .field(|f| f.name("Owner").value(&format!("<@!{}>", guild.owner_id.0)))
+                .field(|f| f.name("Members").value(&guild.member_count.to_string()))
+                .field(|f| f.name("Channels").value(&guild.channels.len().to_string()))
+                .field(|f| f.name("Roles").value(&guild.roles.len().to_string()))
+                .field(|f| f.name("Created").value(&time_ago(&created_at)))
+                .footer(|f| f.text(&format!("ID: {}", guild.id.0)))
+                .timestamp(timestamp_to_string(&created_at));
+            match guild.icon_url() {
+                Some(icon_url) => e.thumbnail(|t| t.url(&icon_url)),
+                None => e,
+            }
+        })
+    }));
+});
@@ -0,0 +1,23 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `shutdown` command, which gracefully stops
+//! the bot, saving any unsaved data first.
+
+use util::{check_msg, is_owner};
+
+command!(shutdown(context, message, _args) {
+    if !is_owner(message.author.id) {
+        return Err("You do not have permission to do that.".to_owned());
+    }
+
+    info!("Shutdown requested by {}", message.author.name);
+    check_msg(context.say("Shutting down."));
+    ::shutdown(&context.data);
+});
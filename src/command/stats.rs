@@ -9,81 +9,177 @@
 
 extern crate psutil;
 
-use ::{CONFIG, UPTIME};
+use ::{COUNTER_PATH, UPTIME, current_config};
 use chrono::UTC;
-use serenity::client::CACHE;
-use serenity::model::{Guild, GuildChannel, UserId};
-use util::{check_msg, duration_to_string, timestamp_to_string};
+use counter::CommandCounter;
+use serenity::client::{CACHE, Context};
+use serenity::model::{Guild, Message, UserId};
+use util::{check_msg, duration_to_string, is_owner, resident_memory, timestamp_to_string};
 
 const BYTES_TO_MEGABYTES: f64 = 1f64 / (1024f64 * 1024f64);
 
-command!(stats(context, message, _args) {
-    let current_time = UTC::now();
-    let cache = match CACHE.read() {
-        Ok(cache) => cache,
-        Err(_) => return Err("Failed to lock cache".to_owned()),
-    };
-    let guilds = cache.guilds
-        .values()
-        .collect::<Vec<&Guild>>();
-    let guilds_count = guilds.len();
-    let channels = guilds.iter()
-        .flat_map(|g| g.channels.values())
-        .collect::<Vec<&GuildChannel>>();
-    let channels_count = channels.len();
-    let mut user_ids = guilds.iter()
-        .flat_map(|g| g.members.keys())
-        .collect::<Vec<&UserId>>();
-    user_ids.sort();
-    user_ids.dedup();
-    let users_count = user_ids.len();
+command!(stats(context, message, args) {
+    match args.first().map(String::as_ref) {
+        Some("reset") => return reset(context, message),
+        Some("system") => return system(context, message),
+        Some("user") => return user(context, message),
+        _ => {},
+    }
 
+    const MAX_COMMANDS: usize = 5;
+
+    let current_time = UTC::now();
     let uptime = current_time - *UPTIME;
 
-    let processes = match psutil::process::all() {
-        Ok(processes) => processes,
-        Err(_) => return Err("Failed to read process list".to_owned()),
+    let (commands_run, counting_since, mut counts) = {
+        let data = context.data.lock().expect("Failed to lock context data");
+        let counter = data.get::<CommandCounter>().unwrap();
+        let counts = counter.commands
+            .iter()
+            .map(|(command, count)| (command.clone(), *count))
+            .collect::<Vec<(String, u64)>>();
+        (counter.commands.values().sum::<u64>(), counter.since, counts)
     };
-    let process = match processes.iter().find(|p| p.pid == psutil::getpid()) {
-        Some(process) => process,
-        None => return Err("Failed to retrieve information on process".to_owned()),
+    // Sort by descending count, breaking ties alphabetically.
+    counts.sort_by(|a, b| (b.1, &a.0).cmp(&(a.1, &b.0)));
+    let most_used = if counts.is_empty() {
+        "None".to_owned()
+    } else {
+        counts.iter()
+            .take(MAX_COMMANDS)
+            .map(|&(ref command, count)| format!("{}: {}", command, count))
+            .collect::<Vec<String>>()
+            .join("\n")
     };
-    let threads = process.num_threads;
-    let memory = match process.memory() {
-        Ok(memory) => memory,
-        Err(_) => return Err("Failed to retrieve process memory usage".to_owned()),
+
+    let config = current_config();
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            e.title(&format!("{} stats", config.bot_name))
+                .field(|f| {
+                    f.name("Commands Run")
+                        .value(&format!("{} in {}",
+                                        commands_run,
+                                        duration_to_string(&(current_time - counting_since))))
+                })
+                .field(|f| f.name("Most Used").value(&most_used))
+                .field(|f| f.name("Uptime").value(&duration_to_string(&uptime)))
+                .field(|f| f.name("Source").value(&config.source_url))
+                .footer(|f| f.text("Use \"stats system\" for system information."))
+                .timestamp(timestamp_to_string(&current_time))
+        })
+    }));
+});
+
+/// Shows information about the bot's process and what it has cached.
+fn system(context: &Context, message: &Message) -> Result<(), String> {
+    let current_time = UTC::now();
+    let (guilds_count, channels_count, users_count, shard_count) = {
+        let cache = match CACHE.read() {
+            Ok(cache) => cache,
+            Err(_) => return Err("Failed to lock cache".to_owned()),
+        };
+        let guilds = cache.guilds
+            .values()
+            .collect::<Vec<&Guild>>();
+        let channels_count = guilds.iter()
+            .flat_map(|g| g.channels.values())
+            .count();
+        let mut user_ids = guilds.iter()
+            .flat_map(|g| g.members.keys())
+            .collect::<Vec<&UserId>>();
+        user_ids.sort();
+        user_ids.dedup();
+        (guilds.len(), channels_count, user_ids.len(), cache.shard_count)
     };
 
-    let total_mem;
-    let resident_mem;
-    let shared_mem;
+    let pid = psutil::getpid();
+    let threads = psutil::process::all()
+        .ok()
+        .and_then(|processes| processes.into_iter().find(|p| p.pid == pid))
+        .map(|process| process.num_threads.to_string())
+        .unwrap_or_else(|| "Unavailable".to_owned());
     #[cfg_attr(feature = "clippy", allow(cast_precision_loss))]
-    {
-        total_mem = memory.size as f64 * BYTES_TO_MEGABYTES;
-        resident_mem = memory.resident as f64 * BYTES_TO_MEGABYTES;
-        shared_mem = memory.share as f64 * BYTES_TO_MEGABYTES;
-    }
+    let memory = resident_memory()
+        .map(|bytes| format!("{:.2} MB", round(bytes as f64 * BYTES_TO_MEGABYTES, 2)))
+        .unwrap_or_else(|| "Unavailable".to_owned());
 
+    let config = current_config();
     check_msg(context.send_message(message.channel_id, |m| {
         m.embed(|e| {
-            e.title(&format!("{} stats", CONFIG.bot_name))
-                .field(|f| f.name("Members").value(&users_count.to_string()))
-                .field(|f| f.name("Channels").value(&channels_count.to_string()))
-                .field(|f| f.name("Uptime").value(&duration_to_string(&uptime)))
+            e.title(&format!("{} system stats", config.bot_name))
                 .field(|f| f.name("Servers").value(&guilds_count.to_string()))
-                .field(|f| f.name("Thread Count").value(&threads.to_string()))
-                .field(|f| {
-                    f.name("Memory Usage")
-                        .value(&format!("Total: {:.2} MB\nResident: {:.2} MB\nShared: {:.2} MB",
-                                        round(total_mem, 2),
-                                        round(resident_mem, 2),
-                                        round(shared_mem, 2)))
-                })
-                .field(|f| f.name("Source").value(&CONFIG.source_url))
+                .field(|f| f.name("Channels").value(&channels_count.to_string()))
+                .field(|f| f.name("Cached Users").value(&users_count.to_string()))
+                .field(|f| f.name("Shards").value(&shard_count.to_string()))
+                .field(|f| f.name("Thread Count").value(&threads))
+                .field(|f| f.name("Memory Usage").value(&memory))
                 .timestamp(timestamp_to_string(&current_time))
         })
     }));
-});
+
+    Ok(())
+}
+
+/// Clears all command counts, including those of each user, restarting the
+/// count from the current time.
+fn reset(context: &Context, message: &Message) -> Result<(), String> {
+    if !is_owner(message.author.id) {
+        return Err("You do not have permission to do that.".to_owned());
+    }
+
+    let cleared = {
+        let mut data = context.data.lock().expect("Failed to lock context data");
+        let counter = data.get_mut::<CommandCounter>().unwrap();
+        let cleared = counter.commands.len();
+        counter.commands.clear();
+        counter.users.clear();
+        counter.since = UTC::now();
+        if let Err(err) = counter.save(&COUNTER_PATH) {
+            warn!("Failed to save command counts: {}", err);
+        }
+        cleared
+    };
+
+    check_msg(context.say(&format!("Cleared {} command count entries.", cleared)));
+
+    Ok(())
+}
+
+/// Lists the commands most frequently run by the mentioned user.
+fn user(context: &Context, message: &Message) -> Result<(), String> {
+    const MAX_COMMANDS: usize = 10;
+
+    let user = match message.mentions.first() {
+        Some(user) => user,
+        None => return Err("Please mention a user to get stats for.".to_owned()),
+    };
+
+    let counts = {
+        let data = context.data.lock().expect("Failed to lock context data");
+        let counter = data.get::<CommandCounter>().unwrap();
+        counter.user_counts(user.id)
+    };
+
+    if counts.is_empty() {
+        check_msg(context.say(&format!("{} has not run any commands.", user.name)));
+        return Ok(());
+    }
+
+    let description = counts.iter()
+        .take(MAX_COMMANDS)
+        .map(|&(ref command, count)| format!("{}: {}", command, count))
+        .collect::<Vec<String>>()
+        .join("\n");
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            e.title(&format!("Top commands for {}", user.name))
+                .description(&description)
+        })
+    }));
+
+    Ok(())
+}
 
 /// Rounds a number to the specified decimal precision.
 #[inline]
@@ -1,9 +1,26 @@
 #[derive(Clone, Debug, Deserialize, Serialize)]
 struct Tag {
+    /// The lowercased name by which the tag is looked up.
     name: String,
+    /// The name of the tag as it was originally cased.
+    #[serde(default)]
+    display_name: Option<String>,
     content: String,
     owner_id: u64,
     uses: u32,
     location: Option<String>,
     created_at: DateTime<UTC>,
+    /// The name of the tag this tag is an alias of, if any.
+    #[serde(default)]
+    points_to: Option<String>,
+    /// The lowercased name of the category the tag is filed under, if any.
+    #[serde(default)]
+    category: Option<String>,
+}
+
+/// The layout in which the tags of a location are saved.
+#[derive(Debug, Deserialize, Serialize)]
+struct TagFile {
+    version: u32,
+    tags: HashMap<String, Tag>,
 }
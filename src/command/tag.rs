@@ -9,23 +9,53 @@
 
 //! Provides functionality for the `tag` command.
 
-extern crate uuid;
-
+use ::current_config;
 use chrono::{DateTime, UTC};
-use self::uuid::Uuid;
-use serde_json;
-use serenity::client::{Context, rest};
+use command::pagination;
+use error::{CommandError, CommandResult, Error as SmexybotError, Result as SmexybotResult};
+use hyper::status::StatusCode;
+use rand::{self, Rng};
+use serde_json::{self, Value};
+use serenity::Error as SerenityError;
+use serenity::client::{CACHE, ClientError, Context, rest};
 use serenity::model::{GuildId, Message, UserId};
 use serenity::utils::builder::CreateEmbed;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
-use std::io::{ErrorKind, Read, Write};
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use util::{check_msg, merge, timestamp_to_string};
+use std::thread;
+use std::time::Duration;
+use util::{check_msg, check_msg_retry, colour_from_hash, data_path, is_guild_admin, is_owner,
+           merge, paginate, time_ago, timestamp_to_string, timestamp_to_string_tz,
+           write_atomically};
+
+/// The number of times to try posting a tag's content.
+const SEND_ATTEMPTS: u32 = 3;
+/// The directory in which each guild's tags, and the generic tags, are
+/// stored in separate files.
+const TAGS_DIR: &'static str = "tags";
+/// The file in which all tags used to be stored.
+const LEGACY_TAGS_FILE: &'static str = "tags.json";
+/// The layout version of tag files written by this version of the bot.
+const TAGS_VERSION: u32 = 1;
+/// The category under which tags without one are listed.
+const UNCATEGORIZED: &'static str = "Uncategorized";
+/// The number of seconds between saves of modified tags.
+const SAVE_INTERVAL: u64 = 5;
+/// The size, in bytes, of the largest file `tag import` accepts.
+const MAX_IMPORT_SIZE: u64 = 1024 * 1024;
+/// The subcommands of the `tag` command, which tags may not be named after
+/// as they could only be posted with `tag get`.
+const SUBCOMMANDS: &'static [&'static str] = &["alias", "categories", "claim", "create", "delete",
+                                               "edit", "export", "get", "import", "info", "list",
+                                               "random", "raw", "rename", "search", "top",
+                                               "transfer"];
 
 lazy_static! {
     static ref TAGS: Tags = Tags {
-        config: Mutex::new(Config::new("tags.json")),
+        config: Mutex::new(Config::new(&data_path(TAGS_DIR))),
     };
 }
 
@@ -36,28 +66,44 @@ include!("tag.in.rs");
 include!(concat!(env!("OUT_DIR"), "/tag.rs"));
 
 impl Tag {
+    /// Creates a new tag. The tag is looked up by the lowercased version of
+    /// `name`, but displayed with its original casing.
     fn new(
         name: String,
         content: String,
         owner_id: u64,
         uses: Option<u32>,
         location: Option<String>,
-        created_at: Option<DateTime<UTC>>
+        created_at: Option<DateTime<UTC>>,
+        category: Option<String>
     ) -> Self {
         Tag {
-            name: name,
+            name: name.to_lowercase(),
+            display_name: Some(name),
             content: content,
             owner_id: owner_id,
             uses: uses.unwrap_or(0),
             location: location,
             created_at: created_at.unwrap_or_else(UTC::now),
+            points_to: None,
+            category: category,
         }
     }
 
     fn as_embed(&self, embed: CreateEmbed) -> CreateEmbed {
-        embed.title(&self.name)
-            .field(|f| f.name("Owner").value(&format!("<@!{}>", self.owner_id)))
+        let mut embed = embed.title(self.display_name()).colour(colour_from_hash(&self.name));
+        if let Some(ref target) = self.points_to {
+            embed = embed.field(|f| f.name("Alias For").value(target));
+        }
+        embed = embed.field(|f| f.name("Category").value(self.category_name()));
+
+        embed.field(|f| f.name("Owner").value(&format!("<@!{}>", self.owner_id)))
             .field(|f| f.name("Uses").value(&self.uses.to_string()))
+            .field(|f| {
+                f.name("Created").value(&format!("{} ({})",
+                                                 time_ago(&self.created_at),
+                                                 timestamp_to_string_tz(&self.created_at)))
+            })
             .author(|a| {
                 let owner_id = UserId(self.owner_id);
                 let (name, avatar_url) = match owner_id.find() {
@@ -85,6 +131,16 @@ impl Tag {
             })
     }
 
+    /// Returns the name of the tag as it should be displayed to users.
+    fn display_name(&self) -> &str {
+        self.display_name.as_ref().unwrap_or(&self.name)
+    }
+
+    /// Returns the name of the category the tag is listed under.
+    fn category_name(&self) -> &str {
+        self.category.as_ref().map_or(UNCATEGORIZED, String::as_str)
+    }
+
     fn is_generic(&self) -> bool {
         self.location.is_none()
     }
@@ -92,18 +148,31 @@ impl Tag {
 
 #[derive(Debug)]
 struct Config {
-    name: String,
+    /// The directory holding one file of tags per location.
+    dir: PathBuf,
     tags: HashMap<String, HashMap<String, Tag>>,
+    /// The locations modified since they were last saved.
+    dirty: HashSet<String>,
 }
 
 impl Config {
-    fn new(name: &str) -> Self {
+    fn new(dir: &str) -> Self {
         let mut config = Config {
-            name: name.to_owned(),
+            dir: PathBuf::from(dir),
             tags: HashMap::new(),
+            dirty: HashSet::new(),
         };
 
-        config.load();
+        let legacy = data_path(LEGACY_TAGS_FILE);
+        if let Err(err) = config.migrate(&legacy) {
+            warn!("Failed to migrate tags from \"{}\": {}", legacy, err);
+        }
+
+        // A missing or corrupt tags file should not take down the bot, so
+        // start with an empty set of tags instead.
+        if let Err(err) = config.load() {
+            warn!("Failed to load tags from \"{}\": {}", dir, err);
+        }
 
         config
     }
@@ -112,39 +181,140 @@ impl Config {
         self.tags.get(key)
     }
 
-    fn insert(&mut self, key: String, value: HashMap<String, Tag>) {
-        self.tags.insert(key, value);
-        self.save();
+    // Returns the location of the database from which the tag with the
+    // specified name is resolved: the guild's own database if it has one by
+    // that name, and the generic database otherwise.
+    fn location_of(&self, guild: Option<GuildId>, name: &str) -> String {
+        let location = get_database_location(guild);
+        if self.get(&location).map_or(false, |tags| tags.contains_key(name)) {
+            location
+        } else {
+            "generic".to_owned()
+        }
+    }
+
+    fn path(&self, location: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", location))
+    }
+
+    fn load(&mut self) -> SmexybotResult<()> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            // If no directory is present, assume this is a fresh config.
+            Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let location = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(location) => location.to_owned(),
+                None => continue,
+            };
+
+            let mut tags = String::new();
+            File::open(&path)?.read_to_string(&mut tags)?;
+            let (tags, outdated) = parse_tags(&tags)?;
+            // Rewrite the tags in the current layout at the next save.
+            if outdated {
+                info!("Migrating tags in \"{}\" to version {}", path.display(), TAGS_VERSION);
+                self.dirty.insert(location.clone());
+            }
+            self.tags.insert(location, tags);
+        }
+        debug!("Loaded config from: {}", self.dir.display());
+
+        Ok(())
     }
 
-    fn load(&mut self) {
-        let mut file = match File::open(&self.name) {
+    // Splits the single file all tags used to be stored in into one file per
+    // location, then renames it so that this only happens once.
+    fn migrate(&self, name: &str) -> SmexybotResult<()> {
+        let mut file = match File::open(name) {
             Ok(file) => file,
-            // If no file is present, assume this is a fresh config.
-            Err(ref err) if err.kind() == ErrorKind::NotFound => return,
-            Err(_) => panic!("Failed to open file: {}", self.name),
+            Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
         };
         let mut tags = String::new();
-        file.read_to_string(&mut tags)
-            .expect(&format!("Failed to read from file: {}", self.name));
-        self.tags = serde_json::from_str(&tags).expect("Failed to deserialize Config");
-        debug!("Loaded config from: {}", self.name);
+        file.read_to_string(&mut tags)?;
+        let tags: HashMap<String, HashMap<String, Tag>> = serde_json::from_str(&tags)?;
+
+        fs::create_dir_all(&self.dir)?;
+        for (location, tags) in &tags {
+            let path = self.path(location);
+            // Never overwrite tags which have been saved since.
+            if path.exists() {
+                continue;
+            }
+            write_tags(&path, tags)?;
+        }
+        fs::rename(name, format!("{}.bak", name))?;
+        info!("Migrated tags from \"{}\" to \"{}\"", name, self.dir.display());
+
+        Ok(())
     }
 
-    fn save(&self) {
-        let temp = format!("{}-{}.tmp", Uuid::new_v4(), self.name);
-        let mut file = File::create(&temp).expect(&format!("Failed to create file: {}", temp));
-        file.write_all(serde_json::to_string(&self.tags)
-                .expect("Failed to serialize Config")
-                .as_bytes())
-            .expect(&format!("Failed to write to file: {}", temp));
+    // Saves the tags of a single location.
+    fn save(&self, location: &str) -> SmexybotResult<()> {
+        let tags = match self.tags.get(location) {
+            Some(tags) => tags,
+            None => return Ok(()),
+        };
+        let path = self.path(location);
+        fs::create_dir_all(&self.dir)?;
+        write_tags(&path, tags)?;
+        trace!("Saved config to: {}", path.display());
 
-        // Atomically copy the new config.
-        fs::rename(temp, &self.name).expect("Failed to write new Config");
-        trace!("Saved config to: {}", self.name);
+        Ok(())
+    }
+
+    // Saves every location modified since it was last saved, and returns how
+    // many were saved. A location which fails to save stays modified, so that
+    // saving it is retried later.
+    fn flush(&mut self) -> SmexybotResult<usize> {
+        let dirty = self.dirty.iter().cloned().collect::<Vec<String>>();
+        for location in &dirty {
+            self.save(location)?;
+            self.dirty.remove(location);
+        }
+
+        Ok(dirty.len())
     }
 }
 
+// Reads the tags of a location, along with whether they were saved in an
+// older layout.
+fn parse_tags(contents: &str) -> SmexybotResult<(HashMap<String, Tag>, bool)> {
+    let value: Value = serde_json::from_str(contents)?;
+
+    // Before versioning, the file held only the tags themselves. A tag named
+    // "version" would be an object, not a number, so cannot be mistaken for
+    // the version.
+    let version = match value.find("version").and_then(Value::as_u64) {
+        Some(version) => version,
+        None => return Ok((serde_json::from_value(value)?, true)),
+    };
+    if version > u64::from(TAGS_VERSION) {
+        warn!("Tags are version {}, but only version {} is supported", version, TAGS_VERSION);
+    }
+    let file: TagFile = serde_json::from_value(value)?;
+
+    Ok((file.tags, false))
+}
+
+fn write_tags(path: &Path, tags: &HashMap<String, Tag>) -> SmexybotResult<()> {
+    let file = TagFile {
+        version: TAGS_VERSION,
+        tags: tags.clone(),
+    };
+    write_atomically(path, serde_json::to_string(&file)?.as_bytes())?;
+
+    Ok(())
+}
+
 #[derive(Debug)]
 struct Tags {
     config: Mutex<Config>,
@@ -168,39 +338,160 @@ impl Tags {
         }
     }
 
-    fn get_tag(&self, guild: Option<GuildId>, name: String) -> Result<Tag, String> {
+    // Retrieves the tag with the specified name, resolving it to its target if
+    // it is an alias.
+    fn get_tag(&self, guild: Option<GuildId>, name: String) -> CommandResult<Tag> {
+        let tag = self.get_raw_tag(guild, name)?;
+        match tag.points_to {
+            Some(target) => {
+                self.get_raw_tag(guild, target)
+                    .map_err(|_| {
+                        CommandError::NotFound("Alias points to a tag which no longer exists"
+                            .to_owned())
+                    })
+            },
+            None => Ok(tag),
+        }
+    }
+
+    // Retrieves the tag with the specified name, without resolving aliases.
+    fn get_raw_tag(&self, guild: Option<GuildId>, name: String) -> CommandResult<Tag> {
         self.get_possible_tags(guild)
-            .get(&name)
+            .get(&name.to_lowercase())
             .cloned()
-            .ok_or_else(|| "Tag not found".to_owned())
-    }
-
-    fn put_tag(&self, guild: Option<GuildId>, name: String, tag: Tag) {
-        // Load the actual tag so we can modify it.
-        let mut config = TAGS.config
-            .lock()
-            .expect("Failed to lock Config");
-        {
-            let database = config.tags
-                .get_mut(&get_database_location(guild))
-                .unwrap();
-            database.insert(name, tag);
-        }
-        config.save();
-    }
-
-    fn delete_tag(&self, guild: Option<GuildId>, name: &str) {
-        let mut config = TAGS.config
-            .lock()
-            .expect("Failed to lock Config");
-        {
-            let database = config.tags
-                .get_mut(&get_database_location(guild))
-                .unwrap();
-            database.remove(name);
+            .ok_or_else(|| CommandError::NotFound("Tag not found".to_owned()))
+    }
+
+    // Returns the names of all aliases which point to the specified tag.
+    fn get_aliases(&self, guild: Option<GuildId>, name: &str) -> Vec<String> {
+        let mut aliases = self.get_possible_tags(guild)
+            .into_iter()
+            .filter(|&(_, ref tag)| tag.points_to.as_ref().map(String::as_ref) == Some(name))
+            .map(|(alias, _)| alias)
+            .collect::<Vec<String>>();
+        aliases.sort();
+        aliases
+    }
+
+    // Runs `f` against the tag database at `location` while holding the lock,
+    // so that concurrent modifications can never overwrite one another. The
+    // database is saved by the next flush, unless `f` fails.
+    fn modify<F, T>(&self, location: &str, f: F) -> CommandResult<T>
+        where F: FnOnce(&mut HashMap<String, Tag>) -> CommandResult<T>
+    {
+        let mut config = self.config.lock().expect("Failed to lock Config");
+        let result = f(config.tags.entry(location.to_owned()).or_insert_with(HashMap::new))?;
+        config.dirty.insert(location.to_owned());
+
+        Ok(result)
+    }
+
+    // Like `modify`, but runs `f` against whichever database holds the tag
+    // with the specified name: the guild's own database if it has one by that
+    // name, and the generic database otherwise.
+    fn modify_containing<F, T>(&self, guild: Option<GuildId>, name: &str, f: F) -> CommandResult<T>
+        where F: FnOnce(&mut HashMap<String, Tag>) -> CommandResult<T>
+    {
+        let mut config = self.config.lock().expect("Failed to lock Config");
+        let location = config.location_of(guild, name);
+
+        let result = match config.tags.get_mut(&location) {
+            Some(database) if database.contains_key(name) => f(database)?,
+            _ => return Err(CommandError::NotFound("Tag not found".to_owned())),
+        };
+        config.dirty.insert(location);
+
+        Ok(result)
+    }
+
+    // Runs `f` against the tag with the specified name, without resolving
+    // aliases, then returns the updated tag.
+    fn modify_tag<F>(&self, guild: Option<GuildId>, name: &str, f: F) -> CommandResult<Tag>
+        where F: FnOnce(&mut Tag) -> CommandResult<()>
+    {
+        self.modify_containing(guild, name, |database| {
+            let tag = database.get_mut(name)
+                .ok_or_else(|| CommandError::NotFound("Tag not found".to_owned()))?;
+            f(tag)?;
+            Ok(tag.clone())
+        })
+    }
+
+    // Points the aliases of a tag which was renamed at its new name, in every
+    // database from which the tag can be resolved: a generic tag can be
+    // resolved from any guild which has no tag of its own by the old name.
+    fn repoint_aliases(&self, guild: Option<GuildId>, old_name: &str, new_name: &str) {
+        let mut config = self.config.lock().expect("Failed to lock Config");
+        let location = config.location_of(guild, new_name);
+
+        let mut repointed = Vec::new();
+        for (key, database) in &mut config.tags {
+            if *key != location && (location != "generic" || database.contains_key(old_name)) {
+                continue;
+            }
+
+            let mut changed = false;
+            for alias in database.values_mut() {
+                if alias.points_to.as_ref().map(String::as_str) == Some(old_name) {
+                    alias.points_to = Some(new_name.to_owned());
+                    changed = true;
+                }
+            }
+            if changed {
+                repointed.push(key.clone());
+            }
         }
-        config.save();
+        config.dirty.extend(repointed);
+    }
+
+    // Retrieves the tag with the specified name, resolving it to its target if
+    // it is an alias, and counts it as used.
+    fn use_tag(&self, guild: Option<GuildId>, name: &str) -> CommandResult<Tag> {
+        let tag = self.get_tag(guild, name.to_lowercase())?;
+        self.modify_tag(guild, &tag.name, |tag| {
+            tag.uses += 1;
+            Ok(())
+        })
+    }
+}
+
+/// Saves any modified tags to disk, e.g. before shutting down. Any
+/// modification in progress is waited for first.
+pub fn save() -> SmexybotResult<()> {
+    let saved = TAGS.config.lock().expect("Failed to lock Config").flush()?;
+    if saved > 0 {
+        trace!("Saved tags of {} locations", saved);
     }
+
+    Ok(())
+}
+
+/// Reads the tags stored in the specified data directory, without migrating
+/// or otherwise modifying them, and returns how many there are.
+pub fn check(data_dir: &str) -> SmexybotResult<usize> {
+    let mut config = Config {
+        dir: Path::new(data_dir).join(TAGS_DIR),
+        tags: HashMap::new(),
+        dirty: HashSet::new(),
+    };
+    config.load()?;
+
+    Ok(config.tags.values().map(HashMap::len).sum())
+}
+
+/// Starts the background thread which saves modified tags to disk. Rapid
+/// modifications are coalesced into a single save, at most `SAVE_INTERVAL`
+/// seconds after the first of them.
+pub fn start() {
+    thread::spawn(|| {
+        loop {
+            thread::sleep(Duration::from_secs(SAVE_INTERVAL));
+
+            if let Err(err) = save() {
+                warn!("Failed to save tags: {}", err);
+            }
+        }
+    });
 }
 
 command!(tag(context, message, args) {
@@ -210,26 +501,21 @@ command!(tag(context, message, args) {
         Some("create") => create,
         Some("info") => info,
         Some("list") => list,
+        Some("categories") => categories,
         Some("edit") => edit,
         Some("delete") => delete,
-        Some(name) => {
-            return {
-                let guild_id = message.guild_id();
-
-                let lookup = name.to_lowercase();
-                match TAGS.get_tag(guild_id, lookup.clone()) {
-                    Ok(tag) => {
-                        let mut tag = tag.clone();
-                        tag.uses += 1;
-                        TAGS.put_tag(guild_id, lookup, tag.clone());
-                        check_msg(context.say(&tag.content));
-
-                        Ok(())
-                    },
-                    Err(err) => Err(err),
-                }
-            };
-        },
+        Some("rename") => rename,
+        Some("alias") => alias,
+        Some("transfer") => transfer,
+        Some("claim") => claim,
+        Some("search") => search,
+        Some("random") => random,
+        Some("raw") => raw,
+        Some("top") => top,
+        Some("export") => export,
+        Some("import") => import,
+        Some("get") => get,
+        Some(name) => return get(context, message, vec![name.to_owned()]).map_err(Into::into),
         None => {
             return Err("Either specify a tag name or use one of the available commands."
                 .to_owned());
@@ -238,157 +524,688 @@ command!(tag(context, message, args) {
 
     // This is necessary because the `command!` macro returns `Ok(())`. Without
     // this match and fall-through, rustc would complain about unreachable code.
-    match f(context, message, args.collect()) {
-        Ok(()) => {},
-        v => return v,
+    if let Err(err) = f(context, message, args.collect()) {
+        return Err(err.into());
     }
 });
 
-pub fn create(context: &Context, message: &Message, args: Vec<String>) -> Result<(), String> {
+pub fn create(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
     let mut args = args.into_iter();
 
     let name = match args.next() {
         Some(name) => name,
-        None => return Err("Please specify a name for the tag.".to_owned()),
+        None => return Err("Please specify a name for the tag.".into()),
+    };
+
+    let mut args = args.peekable();
+    let category = if args.peek().map(String::as_ref) == Some("--category") {
+        args.next();
+        match args.next() {
+            Some(category) => {
+                let category = category.trim().to_lowercase();
+                verify_category_name(&category)?;
+                Some(category)
+            },
+            None => return Err("Please specify a name for the category.".into()),
+        }
+    } else {
+        None
     };
 
     let content = args.collect::<Vec<String>>();
     let content = if content.is_empty() {
-        return Err("Please specify some content for the tag.".to_owned());
+        return Err("Please specify some content for the tag.".into());
     } else {
         content.join(" ")
     };
+    verify_tag_content(&content)?;
 
-    let name = name.trim().to_lowercase().to_owned();
+    let display_name = name.trim().to_owned();
+    let name = display_name.to_lowercase();
     verify_tag_name(&name)?;
 
     let location = get_database_location(message.guild_id());
-    let mut config = TAGS.config.lock().expect("Failed to lock Config");
-    let mut database = config.get(&location)
-        .cloned()
-        .unwrap_or_else(HashMap::new);
-    if database.contains_key(&name) {
-        return Err("Tag already exists.".to_owned());
-    }
+    let tag = Tag::new(display_name.clone(),
+                       content,
+                       message.author.id.0,
+                       None,
+                       Some(location.clone()),
+                       None,
+                       category);
+    TAGS.modify(&location, |database| {
+            if database.contains_key(&name) {
+                return Err("Tag already exists.".into());
+            }
 
-    database.insert(name.clone(),
-                    Tag::new(name.clone(),
-                             content,
-                             message.author.id.0,
-                             None,
-                             Some(location.clone()),
-                             None));
-    config.insert(location, database);
-    check_msg(context.say(&format!("Tag \"{}\" successfully created.", name)));
+            database.insert(name, tag);
+            Ok(())
+        })?;
+    check_msg(context.say(&format!("Tag \"{}\" successfully created.", display_name)));
 
     Ok(())
 }
 
-pub fn info(context: &Context, message: &Message, args: Vec<String>) -> Result<(), String> {
+/// Posts the content of the named tag. Unlike `tag <name>`, the name is never
+/// taken to be a subcommand, so this also posts tags which are named after
+/// one.
+pub fn get(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
+    let name = match args.into_iter().next() {
+        Some(name) => name,
+        None => return Err("Please specify the name of the tag to post.".into()),
+    };
+
+    let tag = TAGS.use_tag(message.guild_id(), &name)?;
+    check_msg_retry(|| context.say(&tag.content), SEND_ATTEMPTS);
+
+    Ok(())
+}
+
+pub fn info(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
     let mut args = args.into_iter();
 
     let name = match args.next() {
         Some(name) => name,
-        None => return Err("Please specify a name for the tag to get info on.".to_owned()),
+        None => return Err("Please specify a name for the tag to get info on.".into()),
     };
 
     let name = name.trim().to_lowercase().to_owned();
     let guild_id = message.guild_id();
-    let tag = TAGS.get_tag(guild_id, name)?;
+    let tag = TAGS.get_raw_tag(guild_id, name)?;
 
     check_msg(context.send_message(message.channel_id, |m| m.embed(|e| tag.as_embed(e))));
 
     Ok(())
 }
 
-pub fn list(context: &Context, message: &Message, _args: Vec<String>) -> Result<(), String> {
+pub fn raw(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
+    let mut args = args.into_iter();
+
+    let name = match args.next() {
+        Some(name) => name,
+        None => return Err("Please specify a name for the tag to view.".into()),
+    };
+
+    let name = name.trim().to_lowercase().to_owned();
+    let guild_id = message.guild_id();
+    let tag = TAGS.get_tag(guild_id, name)?;
+
+    // Break up any backticks with zero-width spaces, so that the content can't
+    // close the code block early.
+    let content = tag.content.replace('`', "`\u{200B}");
+    check_msg(context.say(&format!("```\n{}\n```", content)));
+
+    Ok(())
+}
+
+pub fn list(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
+    // Leave some room in the embed description for the page's formatting.
+    const MAX_PAGE_LENGTH: usize = 1900;
+
+    let mut args = args.into_iter().peekable();
+    // A category may be given before the page number.
+    let category = match args.peek() {
+        Some(arg) if arg.parse::<usize>().is_err() => Some(arg.to_lowercase()),
+        _ => None,
+    };
+    if category.is_some() {
+        args.next();
+    }
+
+    let page = match args.next().map(|page| page.parse::<usize>()) {
+        Some(Ok(0)) | Some(Err(_)) => return Err("Please specify a valid page number.".into()),
+        Some(Ok(page)) => page,
+        None => 1,
+    };
+
     let guild_id = message.guild_id();
     let mut tags = TAGS.get_possible_tags(guild_id);
     let mut tags = tags.drain()
+        .filter(|&(_, ref tag)| {
+            category.as_ref().map_or(true, |category| {
+                tag.category_name().to_lowercase() == *category
+            })
+        })
         .map(|(k, _)| k)
         .collect::<Vec<String>>();
     tags.sort();
 
-    let response = if tags.is_empty() {
-        "No tags available.".to_owned()
-    } else {
-        format!("Available tags: {}", tags.join(", "))
+    if tags.is_empty() {
+        check_msg(context.say(&match category {
+            Some(ref category) => format!("No tags in category \"{}\".", category),
+            None => "No tags available.".to_owned(),
+        }));
+        return Ok(());
+    }
+
+    let pages = paginate(&tags, ", ", MAX_PAGE_LENGTH);
+    if page > pages.len() {
+        return Err(format!("There are only {} pages of tags.", pages.len()).into());
+    }
+    let title = match category {
+        Some(ref category) => format!("Tags in category \"{}\"", category),
+        None => "Available tags".to_owned(),
     };
-    check_msg(context.say(&response));
+    pagination::send(context, message, &title, pages, page - 1);
 
     Ok(())
 }
 
-pub fn edit(context: &Context, message: &Message, args: Vec<String>) -> Result<(), String> {
-    let mut args = args.into_iter();
+pub fn categories(context: &Context, message: &Message, _args: Vec<String>) -> CommandResult<()> {
+    let mut counts = HashMap::new();
+    for tag in TAGS.get_possible_tags(message.guild_id()).values() {
+        *counts.entry(tag.category_name().to_owned()).or_insert(0) += 1;
+    }
 
-    let name = match args.next() {
-        Some(name) => name,
-        None => return Err("Please specify a tag to edit.".to_owned()),
+    if counts.is_empty() {
+        check_msg(context.say("No tags available."));
+        return Ok(());
+    }
+
+    let mut counts = counts.into_iter().collect::<Vec<(String, u32)>>();
+    counts.sort();
+    let description = counts.iter()
+        .map(|&(ref category, count)| format!("**{}**: {} tags", category, count))
+        .collect::<Vec<String>>()
+        .join("\n");
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| e.title("Tag categories").description(&description))
+    }));
+
+    Ok(())
+}
+
+pub fn random(context: &Context, message: &Message, _args: Vec<String>) -> CommandResult<()> {
+    let guild_id = message.guild_id();
+    // Aliases are skipped so that aliased tags aren't more likely to be picked.
+    let tags = TAGS.get_possible_tags(guild_id)
+        .into_iter()
+        .map(|(_, tag)| tag)
+        .filter(|tag| tag.points_to.is_none())
+        .collect::<Vec<Tag>>();
+
+    let name = match rand::thread_rng().choose(&tags) {
+        Some(tag) => tag.name.clone(),
+        None => {
+            check_msg(context.say("No tags available."));
+            return Ok(());
+        },
     };
 
-    let name = name.trim().to_lowercase().to_owned();
+    let tag = TAGS.modify_tag(guild_id, &name, |tag| {
+            tag.uses += 1;
+            Ok(())
+        })?;
+    check_msg_retry(|| context.say(&tag.content), SEND_ATTEMPTS);
+
+    Ok(())
+}
+
+pub fn top(context: &Context, message: &Message, _args: Vec<String>) -> CommandResult<()> {
+    const TAGS_PER_PAGE: usize = 10;
 
     let guild_id = message.guild_id();
-    let mut tag = match TAGS.get_tag(guild_id, name.clone()) {
-        Ok(tag) => tag,
-        Err(err) => return Err(err),
+    let mut tags = TAGS.get_possible_tags(guild_id)
+        .into_iter()
+        .map(|(_, tag)| tag)
+        .filter(|tag| tag.points_to.is_none())
+        .collect::<Vec<Tag>>();
+
+    if tags.is_empty() {
+        check_msg(context.say("No tags available."));
+        return Ok(());
+    }
+
+    // Sort by descending use count, breaking ties alphabetically.
+    tags.sort_by(|a, b| (b.uses, &a.name).cmp(&(a.uses, &b.name)));
+    let pages = tags.iter()
+        .enumerate()
+        .map(|(i, tag)| format!("{}. **{}**: {} uses", i + 1, tag.name, tag.uses))
+        .collect::<Vec<String>>()
+        .chunks(TAGS_PER_PAGE)
+        .map(|lines| lines.join("\n"))
+        .collect::<Vec<String>>();
+    pagination::send(context, message, "Most used tags", pages, 0);
+
+    Ok(())
+}
+
+pub fn search(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
+    const MAX_RESULTS: usize = 100;
+    const RESULTS_PER_PAGE: usize = 10;
+    const SNIPPET_LENGTH: usize = 50;
+
+    let query = if args.is_empty() {
+        return Err("Please specify something to search for.".into());
+    } else {
+        args.join(" ").to_lowercase()
     };
 
-    if !owner_check(message, &tag) {
-        return Err("You do not have permission to do that.".to_owned());
+    let guild_id = message.guild_id();
+    let mut matches = TAGS.get_possible_tags(guild_id)
+        .into_iter()
+        .filter(|&(ref name, ref tag)| {
+            name.contains(&query) || tag.content.to_lowercase().contains(&query)
+        })
+        .collect::<Vec<(String, Tag)>>();
+    matches.sort_by(|a, b| a.0.cmp(&b.0));
+
+    if matches.is_empty() {
+        check_msg(context.say(&format!("No tags matching \"{}\" found.", query)));
+        return Ok(());
     }
 
+    let total = matches.len();
+    let results = matches.into_iter()
+        .take(MAX_RESULTS)
+        .map(|(name, tag)| {
+            let mut snippet = tag.content.chars().take(SNIPPET_LENGTH).collect::<String>();
+            if tag.content.chars().count() > SNIPPET_LENGTH {
+                snippet.push_str("...");
+            }
+            format!("**{}**: {}", name, snippet)
+        })
+        .collect::<Vec<String>>();
+    let mut pages = results.chunks(RESULTS_PER_PAGE)
+        .map(|lines| lines.join("\n"))
+        .collect::<Vec<String>>();
+    if total > MAX_RESULTS {
+        if let Some(page) = pages.last_mut() {
+            page.push_str(&format!("\n... and {} more. Try a more specific search.",
+                                   total - MAX_RESULTS));
+        }
+    }
+    pagination::send(context, message, &format!("Tags matching \"{}\"", query), pages, 0);
+
+    Ok(())
+}
+
+pub fn edit(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
+    let mut args = args.into_iter();
+
+    let name = match args.next() {
+        Some(name) => name,
+        None => return Err("Please specify a tag to edit.".into()),
+    };
+
+    let name = name.trim().to_lowercase().to_owned();
+
     let content = args.collect::<Vec<String>>();
     let content = if content.is_empty() {
-        return Err("Please specify some content for the tag.".to_owned());
+        return Err("Please specify some content for the tag.".into());
     } else {
         content.join(" ")
     };
+    verify_tag_content(&content)?;
 
-    tag.content = content;
-    TAGS.put_tag(guild_id, name.clone(), tag);
+    // Edits go to the tag an alias points to, rather than to the alias itself.
+    let guild_id = message.guild_id();
+    let target = TAGS.get_tag(guild_id, name.clone())?;
+    TAGS.modify_tag(guild_id, &target.name, |tag| {
+            if !owner_check(message, tag) {
+                return Err(CommandError::PermissionDenied);
+            }
+
+            tag.content = content;
+            Ok(())
+        })?;
 
     check_msg(context.say(&format!("Tag \"{}\" successfully updated.", name)));
 
     Ok(())
 }
 
-pub fn delete(context: &Context, message: &Message, args: Vec<String>) -> Result<(), String> {
+pub fn delete(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
     let mut args = args.into_iter();
 
     let name = match args.next() {
         Some(name) => name,
-        None => return Err("Please specify a tag to delete.".to_owned()),
+        None => return Err("Please specify a tag to delete.".into()),
     };
 
     let name = name.trim().to_lowercase().to_owned();
 
     let guild_id = message.guild_id();
-    let tag = match TAGS.get_tag(guild_id, name.clone()) {
-        Ok(tag) => tag,
-        Err(err) => return Err(err),
+    TAGS.modify_containing(guild_id, &name, |database| {
+            if database.get(&name).map_or(false, |tag| !owner_check(message, tag)) {
+                return Err(CommandError::PermissionDenied);
+            }
+
+            database.remove(&name);
+            Ok(())
+        })?;
+
+    let mut response = format!("Tag \"{}\" successfully deleted.", name);
+    let aliases = TAGS.get_aliases(guild_id, &name);
+    if !aliases.is_empty() {
+        response.push_str(&format!("\nWarning: the following aliases no longer point to a tag: {}",
+                                   aliases.join(", ")));
+    }
+    check_msg(context.say(&response));
+
+    Ok(())
+}
+
+pub fn rename(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
+    let mut args = args.into_iter();
+
+    let (old_name, new_name) = match (args.next(), args.next()) {
+        (Some(old_name), Some(new_name)) => (old_name, new_name),
+        _ => return Err("Please specify a tag to rename and its new name.".into()),
+    };
+
+    let old_name = old_name.trim().to_lowercase().to_owned();
+    let new_display_name = new_name.trim().to_owned();
+    let new_name = new_display_name.to_lowercase();
+
+    verify_tag_name(&new_name)?;
+
+    // Also check the names visible from other databases, so that the renamed
+    // tag neither shadows nor is shadowed by another tag.
+    let guild_id = message.guild_id();
+    if TAGS.get_raw_tag(guild_id, new_name.clone()).is_ok() {
+        return Err("Tag already exists.".into());
+    }
+
+    let old_display_name = TAGS.modify_containing(guild_id, &old_name, |database| {
+            if database.contains_key(&new_name) {
+                return Err("Tag already exists.".into());
+            }
+            if database.get(&old_name).map_or(false, |tag| !owner_check(message, tag)) {
+                return Err(CommandError::PermissionDenied);
+            }
+
+            let mut tag = database.remove(&old_name)
+                .ok_or_else(|| CommandError::NotFound("Tag not found".to_owned()))?;
+            let old_display_name = tag.display_name().to_owned();
+            tag.name = new_name.clone();
+            tag.display_name = Some(new_display_name.clone());
+            database.insert(new_name.clone(), tag);
+            Ok(old_display_name)
+        })?;
+    // Keep the tag's aliases pointing at it under its new name.
+    TAGS.repoint_aliases(guild_id, &old_name, &new_name);
+
+    check_msg(context.say(&format!("Tag \"{}\" successfully renamed to \"{}\".",
+                                   old_display_name,
+                                   new_display_name)));
+
+    Ok(())
+}
+
+pub fn alias(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
+    let mut args = args.into_iter();
+
+    let (name, target) = match (args.next(), args.next()) {
+        (Some(name), Some(target)) => (name, target),
+        _ => return Err("Please specify a name for the alias and the tag it points to.".into()),
+    };
+
+    let display_name = name.trim().to_owned();
+    let name = display_name.to_lowercase();
+    let target = target.trim().to_lowercase().to_owned();
+    verify_tag_name(&name)?;
+
+    // Resolve the target first, so that aliases always point directly at a
+    // real tag rather than at another alias.
+    let guild_id = message.guild_id();
+    let target = TAGS.get_tag(guild_id, target)?;
+
+    let location = get_database_location(guild_id);
+    let mut alias = Tag::new(display_name.clone(),
+                             String::new(),
+                             message.author.id.0,
+                             None,
+                             Some(location.clone()),
+                             None,
+                             None);
+    alias.points_to = Some(target.name.clone());
+    TAGS.modify(&location, |database| {
+            if database.contains_key(&name) {
+                return Err("Tag already exists.".into());
+            }
+
+            database.insert(name, alias);
+            Ok(())
+        })?;
+    check_msg(context.say(&format!("Alias \"{}\" for tag \"{}\" successfully created.",
+                                   display_name,
+                                   target.display_name())));
+
+    Ok(())
+}
+
+pub fn transfer(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
+    let mut args = args.into_iter();
+
+    let name = match args.next() {
+        Some(name) => name,
+        None => return Err("Please specify a tag to transfer.".into()),
+    };
+
+    let new_owner = match message.mentions.first() {
+        Some(user) if !user.bot => user,
+        Some(_) => return Err("Tags cannot be transferred to bots.".into()),
+        None => return Err("Please mention the user to transfer the tag to.".into()),
+    };
+
+    let name = name.trim().to_lowercase().to_owned();
+
+    TAGS.modify_tag(message.guild_id(), &name, |tag| {
+            if !owner_check(message, tag) {
+                return Err(CommandError::PermissionDenied);
+            }
+
+            tag.owner_id = new_owner.id.0;
+            Ok(())
+        })?;
+
+    check_msg(context.say(&format!("Tag \"{}\" successfully transferred to {}.",
+                                   name,
+                                   new_owner.name)));
+
+    Ok(())
+}
+
+pub fn claim(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
+    let mut args = args.into_iter();
+
+    let name = match args.next() {
+        Some(name) => name,
+        None => return Err("Please specify a tag to claim.".into()),
+    };
+
+    let name = name.trim().to_lowercase().to_owned();
+
+    let guild_id = message.guild_id();
+    let tag = TAGS.get_raw_tag(guild_id, name.clone())?;
+
+    if owner_check(message, &tag) {
+        return Err("You already own that tag.".into());
+    }
+
+    // Whether the owner has left can only be judged for tags of this server;
+    // generic tags and those of other servers are only claimable by the bot's
+    // owners.
+    let local = match guild_id {
+        Some(guild_id) => tag.location.as_ref() == Some(&guild_id.to_string()),
+        None => false,
     };
+    if !local && !is_owner(message.author.id) {
+        return Err("This tag doesn't belong to this server.".into());
+    }
 
-    if !owner_check(message, &tag) {
-        return Err("You do not have permission to do that.".to_owned());
+    // Tags may only be claimed once their owner has left the server, unless
+    // the claimant is one of the bot's owners.
+    if !is_owner(message.author.id) {
+        let orphaned = match guild_id {
+            Some(guild_id) => !is_member(guild_id, UserId(tag.owner_id))?,
+            None => false,
+        };
+        if !orphaned {
+            return Err("That tag's owner is still present in the server.".into());
+        }
     }
 
-    TAGS.delete_tag(guild_id, &name);
+    // The membership check above may hit the REST API, so it is done without
+    // holding the lock; make sure nobody else claimed the tag in the meantime.
+    TAGS.modify_tag(guild_id, &name, |claimed| {
+            if claimed.owner_id != tag.owner_id {
+                return Err("That tag's owner has changed, please try again.".into());
+            }
+
+            claimed.owner_id = message.author.id.0;
+            Ok(())
+        })?;
 
-    check_msg(context.say(&format!("Tag \"{}\" successfully deleted.", name)));
+    check_msg(context.say(&format!("Tag \"{}\" successfully claimed.", name)));
+
+    Ok(())
+}
+
+pub fn export(context: &Context, message: &Message, _args: Vec<String>) -> CommandResult<()> {
+    let guild_id = match message.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Err("Tags can only be exported from a server.".into()),
+    };
+    if !is_guild_admin(guild_id, message.author.id) {
+        return Err(CommandError::PermissionDenied);
+    }
+
+    let location = get_database_location(Some(guild_id));
+    let tags = {
+        let config = TAGS.config.lock().expect("Failed to lock Config");
+        config.get(&location).cloned().unwrap_or_else(HashMap::new)
+    };
+    if tags.is_empty() {
+        return Err("This server has no tags to export.".into());
+    }
+
+    let json = serde_json::to_string_pretty(&tags).map_err(SmexybotError::from)?;
+    let filename = format!("tags-{}.json", guild_id);
+    check_msg(context.send_file(message.channel_id, json.as_bytes(), &filename, |m| {
+        m.content(&format!("Exported {} tags.", tags.len()))
+    }));
+
+    Ok(())
+}
+
+pub fn import(context: &Context, message: &Message, args: Vec<String>) -> CommandResult<()> {
+    let guild_id = match message.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Err("Tags can only be imported into a server.".into()),
+    };
+    if !is_guild_admin(guild_id, message.author.id) {
+        return Err(CommandError::PermissionDenied);
+    }
+
+    // Whether imported tags replace existing tags of the same name, rather
+    // than being skipped.
+    let overwrite = match args.first().map(String::as_ref) {
+        Some("overwrite") => true,
+        Some("skip") | None => false,
+        Some(_) => {
+            return Err("The collision policy must be either \"skip\" or \"overwrite\".".into())
+        },
+    };
+
+    let attachment = match message.attachments.first() {
+        Some(attachment) => attachment,
+        None => return Err("Please attach a JSON file of tags, as made by `tag export`.".into()),
+    };
+    if attachment.size > MAX_IMPORT_SIZE {
+        return Err(format!("The file must be at most {} bytes.", MAX_IMPORT_SIZE).into());
+    }
+
+    let bytes = attachment.download()
+        .map_err(|err| CommandError::from(format!("Failed to download the file: {:?}", err)))?;
+    let tags = serde_json::from_slice::<HashMap<String, Tag>>(&bytes).map_err(|err| {
+            CommandError::from(format!("The file is not a valid tag export: {}", err))
+        })?;
+
+    let location = get_database_location(Some(guild_id));
+    let (imported, skipped, invalid) = TAGS.modify(&location, |database| {
+            let (mut imported, mut skipped, mut invalid) = (0, 0, 0);
+            for (name, mut tag) in tags {
+                let name = name.trim().to_lowercase();
+                if verify_tag_name(&name).is_err() ||
+                   (tag.points_to.is_none() && verify_tag_content(&tag.content).is_err()) {
+                    invalid += 1;
+                    continue;
+                }
+                if !overwrite && database.contains_key(&name) {
+                    skipped += 1;
+                    continue;
+                }
+
+                tag.name = name.clone();
+                tag.location = Some(location.clone());
+                database.insert(name, tag);
+                imported += 1;
+            }
+            Ok((imported, skipped, invalid))
+        })?;
+
+    check_msg(context.say(&format!("Imported {} tags ({} skipped as they already exist, {} \
+                                    invalid).",
+                                   imported,
+                                   skipped,
+                                   invalid)));
 
     Ok(())
 }
 
 // Denies certain tag names from being used as keys.
-fn verify_tag_name(name: &str) -> Result<(), String> {
+fn verify_tag_name(name: &str) -> CommandResult<()> {
     if name.contains("@everyone") || name.contains("@here") {
-        return Err("Tag contains blocked words".to_owned());
+        return Err("Tag contains blocked words".into());
     }
 
     if name.len() > 100 {
-        return Err("Tag name limit is 100 characters".to_owned());
+        return Err("Tag name limit is 100 characters".into());
+    }
+
+    if name.trim().is_empty() {
+        return Err("Tag names cannot be blank".into());
+    }
+
+    // These would break the formatting of tag lists.
+    if name.contains('`') || name.contains('|') {
+        return Err("Tag names cannot contain backticks or pipes".into());
+    }
+
+    if SUBCOMMANDS.contains(&name.to_lowercase().as_str()) {
+        return Err(format!("Tag names cannot be the same as a subcommand (\"{}\")", name).into());
+    }
+
+    Ok(())
+}
+
+// Denies category names which would be confusing or abusive.
+fn verify_category_name(name: &str) -> CommandResult<()> {
+    if name.contains("@everyone") || name.contains("@here") {
+        return Err("Category contains blocked words".into());
+    }
+
+    if name.chars().count() > 32 {
+        return Err("Category name limit is 32 characters".into());
+    }
+
+    if name.parse::<usize>().is_ok() {
+        return Err("Category names cannot be numbers".into());
+    }
+
+    Ok(())
+}
+
+// Denies tag content which is too long to be posted.
+fn verify_tag_content(content: &str) -> CommandResult<()> {
+    let limit = current_config().max_tag_content_length;
+    let length = content.chars().count();
+    if length > limit {
+        return Err(format!("Tag content limit is {} characters (content is {} characters)",
+                           limit,
+                           length)
+            .into());
     }
 
     Ok(())
@@ -398,7 +1215,229 @@ fn owner_check(message: &Message, tag: &Tag) -> bool {
     message.author.id == tag.owner_id
 }
 
+// Checks whether the user is a member of the guild, first via the cache and
+// then via the REST API, as the cache may not contain every member. Only a
+// 404 means the user is absent; any other failure is returned, as the user may
+// well still be a member.
+fn is_member(guild_id: GuildId, user_id: UserId) -> CommandResult<bool> {
+    let cached = CACHE.read()
+        .ok()
+        .and_then(|cache| {
+            cache.guilds
+                .get(&guild_id)
+                .map(|guild| guild.members.contains_key(&user_id))
+        });
+
+    if cached == Some(true) {
+        return Ok(true);
+    }
+
+    match rest::get_member(guild_id.0, user_id.0) {
+        Ok(_) => Ok(true),
+        Err(SerenityError::Client(ClientError::InvalidRequest(StatusCode::NotFound))) => Ok(false),
+        Err(err) => {
+            warn!("Failed to look up member {} of guild {}: {:?}", user_id.0, guild_id.0, err);
+            Err("Failed to check whether the tag's owner is still present in the server, please \
+                 try again later."
+                .into())
+        },
+    }
+}
+
 fn get_database_location(guild: Option<GuildId>) -> String {
     guild.map(|g| g.to_string())
         .unwrap_or_else(|| "generic".to_owned())
 }
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::GuildId;
+    use std::collections::{HashMap, HashSet};
+    use std::env;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use super::{Config, Tag, Tags, verify_tag_name};
+    use uuid::Uuid;
+
+    const GUILD: GuildId = GuildId(1);
+
+    // Creates an empty set of tags which is saved to a fresh temporary
+    // directory, rather than to the data directory.
+    fn temp_tags() -> Tags {
+        Tags {
+            config: Mutex::new(Config {
+                dir: env::temp_dir().join(Uuid::new_v4().to_string()),
+                tags: HashMap::new(),
+                dirty: HashSet::new(),
+            }),
+        }
+    }
+
+    fn insert(tags: &Tags, location: &str, name: &str) {
+        let tag = Tag::new(name.to_owned(),
+                           format!("Content of {}", name),
+                           1,
+                           None,
+                           Some(location.to_owned()),
+                           None,
+                           None);
+        tags.modify(location, |database| {
+                database.insert(name.to_owned(), tag);
+                Ok(())
+            })
+            .expect("Failed to insert tag");
+    }
+
+    // Reads the tags back from the directory they were saved to.
+    fn reload(tags: &Tags) -> Config {
+        let dir = tags.config.lock().unwrap().dir.clone();
+        let mut config = Config {
+            dir: dir,
+            tags: HashMap::new(),
+            dirty: HashSet::new(),
+        };
+        config.load().expect("Failed to load tags");
+        config
+    }
+
+    fn remove_dir(tags: &Tags) {
+        let _ = fs::remove_dir_all(&tags.config.lock().unwrap().dir);
+    }
+
+    #[test]
+    fn concurrent_modifications_are_kept() {
+        const WRITES: u32 = 50;
+
+        let tags = Arc::new(temp_tags());
+        insert(&tags, &GUILD.to_string(), "edited");
+
+        let creator = {
+            let tags = tags.clone();
+            thread::spawn(move || {
+                for i in 0..WRITES {
+                    insert(&tags, &GUILD.to_string(), &format!("created{}", i));
+                }
+            })
+        };
+        let editor = {
+            let tags = tags.clone();
+            thread::spawn(move || {
+                for _ in 0..WRITES {
+                    tags.modify_tag(Some(GUILD), "edited", |tag| {
+                            tag.uses += 1;
+                            Ok(())
+                        })
+                        .expect("Failed to edit tag");
+                }
+            })
+        };
+        creator.join().unwrap();
+        editor.join().unwrap();
+        tags.config.lock().unwrap().flush().expect("Failed to save tags");
+
+        let config = reload(&tags);
+        let database = config.get(&GUILD.to_string()).expect("Guild tags were not saved");
+        for i in 0..WRITES {
+            assert!(database.contains_key(&format!("created{}", i)));
+        }
+        assert_eq!(database["edited"].uses, WRITES);
+        remove_dir(&tags);
+    }
+
+    #[test]
+    fn flush_saves_each_modified_location_once() {
+        let tags = temp_tags();
+        for i in 0..5 {
+            insert(&tags, &GUILD.to_string(), &format!("guild{}", i));
+        }
+        for i in 0..3 {
+            insert(&tags, "generic", &format!("generic{}", i));
+        }
+
+        let mut config = tags.config.lock().unwrap();
+        // The modifications are only saved by the flush, once per location.
+        assert!(!config.path(&GUILD.to_string()).exists());
+        assert!(!config.path("generic").exists());
+        assert_eq!(config.flush().expect("Failed to save tags"), 2);
+        assert!(config.path(&GUILD.to_string()).exists());
+        assert!(config.path("generic").exists());
+        // Nothing was modified since, so there is nothing left to save.
+        assert_eq!(config.flush().expect("Failed to save tags"), 0);
+        drop(config);
+        remove_dir(&tags);
+    }
+
+    #[test]
+    fn verify_tag_names() {
+        let err = verify_tag_name("   ").unwrap_err();
+        assert_eq!(err.to_string(), "Tag names cannot be blank");
+        let err = verify_tag_name("a`b").unwrap_err();
+        assert_eq!(err.to_string(), "Tag names cannot contain backticks or pipes");
+        let err = verify_tag_name("a|b").unwrap_err();
+        assert_eq!(err.to_string(), "Tag names cannot contain backticks or pipes");
+        let err = verify_tag_name("info").unwrap_err();
+        assert_eq!(err.to_string(), "Tag names cannot be the same as a subcommand (\"info\")");
+        assert!(verify_tag_name("rules").is_ok());
+    }
+
+    #[test]
+    fn get_tag_named_after_subcommand() {
+        let tags = temp_tags();
+        insert(&tags, &GUILD.to_string(), "info");
+
+        let tag = tags.use_tag(Some(GUILD), "info").expect("Failed to get tag");
+        assert_eq!(tag.content, "Content of info");
+        assert_eq!(tag.uses, 1);
+    }
+
+    #[test]
+    fn rename_repoints_aliases_of_generic_tags() {
+        let tags = temp_tags();
+        insert(&tags, "generic", "new");
+        insert(&tags, &GUILD.to_string(), "alias");
+        tags.modify_tag(Some(GUILD), "alias", |alias| {
+                alias.points_to = Some("old".to_owned());
+                Ok(())
+            })
+            .unwrap();
+
+        tags.repoint_aliases(Some(GUILD), "old", "new");
+        let alias = tags.get_raw_tag(Some(GUILD), "alias".to_owned()).unwrap();
+        assert_eq!(alias.points_to, Some("new".to_owned()));
+    }
+
+    #[test]
+    fn concurrent_create_and_edit_of_one_tag() {
+        for _ in 0..50 {
+            let tags = Arc::new(temp_tags());
+
+            let creator = {
+                let tags = tags.clone();
+                thread::spawn(move || insert(&tags, &GUILD.to_string(), "shared"))
+            };
+            let editor = {
+                let tags = tags.clone();
+                thread::spawn(move || {
+                    tags.modify_tag(Some(GUILD), "shared", |tag| {
+                            tag.content = "Edited".to_owned();
+                            Ok(())
+                        })
+                        .is_ok()
+                })
+            };
+            creator.join().unwrap();
+            let edited = editor.join().unwrap();
+            tags.config.lock().unwrap().flush().expect("Failed to save tags");
+
+            // The edit either came first and found no tag, or came second and
+            // was kept.
+            let expected = if edited { "Edited" } else { "Content of shared" };
+            let tag = tags.get_raw_tag(Some(GUILD), "shared".to_owned()).unwrap();
+            assert_eq!(tag.content, expected);
+            let config = reload(&tags);
+            assert_eq!(config.get(&GUILD.to_string()).unwrap()["shared"].content, expected);
+            remove_dir(&tags);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `command` command, which allows guild admins
+//! to disable and re-enable commands in their guild, e.g.
+//! `!command disable fuyu`.
+
+use ::{canonical_name, is_command};
+use disabled::DISABLED_COMMANDS;
+use util::{check_msg, is_guild_admin};
+
+/// The name under which this command is registered. It may not be disabled,
+/// as it could then never be enabled again.
+const COMMAND_NAME: &'static str = "command";
+
+command!(command(context, message, args) {
+    let guild_id = match message.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Err("Commands can only be disabled in a server.".to_owned()),
+    };
+
+    let mut args = args.into_iter();
+    let disabled = match args.next().as_ref().map(String::as_ref) {
+        Some("enable") => false,
+        Some("disable") => true,
+        _ => {
+            let disabled = DISABLED_COMMANDS.list(guild_id);
+            check_msg(context.say(&if disabled.is_empty() {
+                "No commands are disabled in this server.".to_owned()
+            } else {
+                format!("Commands disabled in this server: {}", disabled.join(", "))
+            }));
+            return Ok(());
+        },
+    };
+
+    // Commands are disabled under their own name, so that disabling an alias
+    // disables the command it stands for.
+    let name = match args.next() {
+        Some(name) => canonical_name(&name),
+        None => return Err("Please specify a command.".to_owned()),
+    };
+    // A name which is disabled may always be enabled again, in case it was
+    // stored before names were checked or its command is no longer compiled in.
+    let was_disabled = DISABLED_COMMANDS.is_disabled(guild_id, &name);
+    if !is_command(&name) && !(was_disabled && !disabled) {
+        return Err(format!("Unknown command: {}", name));
+    }
+    if disabled && name == COMMAND_NAME {
+        return Err("This command cannot be disabled.".to_owned());
+    }
+
+    if !is_guild_admin(guild_id, message.author.id) {
+        return Err("You do not have permission to do that.".to_owned());
+    }
+
+    DISABLED_COMMANDS.set(guild_id, &name, disabled)
+        .map_err(|err| format!("Failed to save disabled commands: {}", err))?;
+
+    check_msg(context.say(&if disabled {
+        format!("Command \"{}\" disabled.", name)
+    } else {
+        format!("Command \"{}\" enabled.", name)
+    }));
+});
@@ -0,0 +1,20 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `uptime` command.
+
+use ::UPTIME;
+use chrono::UTC;
+use util::{check_msg, duration_to_string};
+
+command!(uptime(context, _message, _args) {
+    let uptime = UTC::now() - *UPTIME;
+
+    check_msg(context.say(&format!("Uptime: {}", duration_to_string(&uptime))));
+});
@@ -0,0 +1,14 @@
+#[derive(Debug, Deserialize)]
+struct UrbanResponse {
+    pub list: Vec<UrbanDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UrbanDefinition {
+    pub word: String,
+    pub definition: String,
+    pub example: String,
+    pub thumbs_up: u64,
+    pub thumbs_down: u64,
+    pub permalink: String,
+}
@@ -0,0 +1,109 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides a command which allows a user to look up the definition of a term
+//! on [Urban Dictionary][urban-dictionary].
+//!
+//! [urban-dictionary]: https://www.urbandictionary.com/
+
+use error::{Error, Result};
+use hyper::Url;
+use hyper::client::Client;
+use hyper::status::StatusCode;
+use serde_json;
+use std::io::Read;
+use util::{check_msg, http_client, random_colour, truncate_words, with_typing};
+
+lazy_static! {
+    static ref URBAN_URL: Url = "https://api.urbandictionary.com/v0/define".parse::<Url>()
+        .unwrap();
+}
+
+#[cfg(feature = "nightly")]
+include!("urban.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/urban.rs"));
+
+/// The outcome of looking up a term.
+#[derive(Debug)]
+enum Lookup {
+    Found(UrbanDefinition),
+    NotFound,
+    RateLimited,
+}
+
+command!(urban(context, message, args) {
+    let term = args.join(" ");
+    if term.trim().is_empty() {
+        return Err("Please specify a term to look up.".to_owned());
+    }
+
+    let definition = match with_typing(context,
+                                       message.channel_id,
+                                       || query_urban(http_client(), &term)) {
+        Ok(Lookup::Found(definition)) => definition,
+        Ok(Lookup::NotFound) => return Err(format!("No definition found for \"{}\".", term)),
+        Ok(Lookup::RateLimited) => {
+            return Err("Urban Dictionary is receiving too many requests, please try again later."
+                .to_owned());
+        },
+        Err(err) => {
+            warn!("Failed to query Urban Dictionary for \"{}\": {}", term, err);
+            return Err("Failed to retrieve the definition, please try again later.".to_owned());
+        },
+    };
+
+    // Discord's embed description and field value limits.
+    let description = truncate_words(&strip_links(&definition.definition), 2000);
+    let example = truncate_words(&strip_links(&definition.example), 1000);
+    let colour = random_colour();
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            let e = e.title(&definition.word)
+                .url(&definition.permalink)
+                .colour(colour)
+                .description(&description);
+            let e = if example.trim().is_empty() {
+                e
+            } else {
+                e.field(|f| f.name("Example").value(&example))
+            };
+            e.footer(|f| {
+                f.text(&format!("👍 {} 👎 {}", definition.thumbs_up, definition.thumbs_down))
+            })
+        })
+    }));
+});
+
+// Urban Dictionary marks references to other terms with square brackets.
+fn strip_links(text: &str) -> String {
+    text.replace('[', "").replace(']', "")
+}
+
+fn query_urban(client: &Client, term: &str) -> Result<Lookup> {
+    let mut url = URBAN_URL.clone();
+    url.query_pairs_mut()
+        .clear()
+        .append_pair("term", term);
+
+    let mut response = client.get(url).send().map_err(Error::from)?;
+    if response.status == StatusCode::TooManyRequests {
+        return Ok(Lookup::RateLimited);
+    }
+
+    let mut result = String::new();
+    response.read_to_string(&mut result).map_err(Error::from)?;
+
+    let response = serde_json::from_str::<UrbanResponse>(&result).map_err(Error::from)?;
+    Ok(match response.list.into_iter().next() {
+        Some(definition) => Lookup::Found(definition),
+        None => Lookup::NotFound,
+    })
+}
@@ -0,0 +1,66 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `userinfo` command, which shows information
+//! on the mentioned user, or on the author of the message if nobody is
+//! mentioned.
+
+use chrono::{DateTime, UTC};
+use serenity::client::{CACHE, rest};
+use serenity::model::{GuildId, UserId};
+use util::{check_msg, random_colour, snowflake_to_timestamp, time_ago, timestamp_to_string};
+
+command!(userinfo(context, message, _args) {
+    let user = message.mentions.first().unwrap_or(&message.author);
+
+    let created_at = snowflake_to_timestamp(user.id.0);
+    let joined_at = message.guild_id().and_then(|guild_id| joined_at(guild_id, user.id));
+    let colour = random_colour();
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            let mut e = e.title(&format!("{}#{}", user.name, user.discriminator))
+                .colour(colour)
+                .field(|f| f.name("ID").value(&user.id.0.to_string()))
+                .field(|f| f.name("Created").value(&time_ago(&created_at)));
+            if let Some(joined_at) = joined_at {
+                e = e.field(|f| f.name("Joined").value(&time_ago(&joined_at)));
+            }
+            if let Some(avatar_url) = user.avatar_url() {
+                e = e.thumbnail(|t| t.url(&avatar_url));
+            }
+            e.timestamp(timestamp_to_string(&created_at))
+        })
+    }));
+});
+
+// Returns when the user joined the guild, first checking the cache and then
+// the REST API, as the cache may not contain every member.
+fn joined_at(guild_id: GuildId, user_id: UserId) -> Option<DateTime<UTC>> {
+    let cached = CACHE.read()
+        .ok()
+        .and_then(|cache| {
+            cache.guilds
+                .get(&guild_id)
+                .and_then(|guild| guild.members.get(&user_id))
+                .map(|member| member.joined_at.clone())
+        });
+    let joined_at = match cached {
+        Some(joined_at) => joined_at,
+        None => {
+            match rest::get_member(guild_id.0, user_id.0) {
+                Ok(member) => member.joined_at,
+                Err(_) => return None,
+            }
+        },
+    };
+
+    DateTime::parse_from_rfc3339(&joined_at)
+        .ok()
+        .map(|joined_at| joined_at.with_timezone(&UTC))
+}
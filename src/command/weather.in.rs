@@ -0,0 +1,35 @@
+#[derive(Debug, Deserialize)]
+struct WeatherResponse {
+    pub name: String,
+    pub weather: Vec<WeatherCondition>,
+    pub main: WeatherMain,
+    #[serde(default)]
+    pub wind: Option<WeatherWind>,
+    #[serde(default)]
+    pub sys: Option<WeatherSys>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherCondition {
+    pub description: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherMain {
+    /// The temperature, in degrees Celsius.
+    pub temp: f64,
+    /// The relative humidity, as a percentage.
+    pub humidity: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherWind {
+    /// The wind speed, in metres per second.
+    pub speed: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct WeatherSys {
+    #[serde(default)]
+    pub country: Option<String>,
+}
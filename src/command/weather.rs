@@ -0,0 +1,112 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides a command which allows a user to look up the current weather at a
+//! location via the [OpenWeatherMap][openweathermap] API.
+//!
+//! [openweathermap]: https://openweathermap.org/current
+
+use ::current_config;
+use error::{Error, Result};
+use hyper::Url;
+use hyper::client::Client;
+use hyper::status::StatusCode;
+use serde_json;
+use std::io::Read;
+use util::{check_msg, http_client, random_colour, with_typing};
+
+lazy_static! {
+    static ref WEATHER_URL: Url = "https://api.openweathermap.org/data/2.5/weather"
+        .parse::<Url>()
+        .unwrap();
+}
+
+#[cfg(feature = "nightly")]
+include!("weather.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/weather.rs"));
+
+command!(weather(context, message, args) {
+    let location = args.join(" ");
+    if location.trim().is_empty() {
+        return Err("Please specify a location (e.g. weather London)".to_owned());
+    }
+
+    let api_key = match current_config().weather_api_key {
+        Some(ref api_key) => api_key.clone(),
+        None => return Err("The weather command has not been configured.".to_owned()),
+    };
+
+    let weather = match with_typing(context,
+                                    message.channel_id,
+                                    || query_weather(http_client(), &location, &api_key)) {
+        Ok(Some(weather)) => weather,
+        Ok(None) => return Err(format!("No location named \"{}\" was found.", location)),
+        Err(err) => {
+            warn!("Failed to query weather for \"{}\": {}", location, err);
+            return Err("Failed to retrieve the weather, please try again later.".to_owned());
+        },
+    };
+
+    let place = match weather.sys.as_ref().and_then(|sys| sys.country.as_ref()) {
+        Some(country) => format!("{}, {}", weather.name, country),
+        None => weather.name.clone(),
+    };
+    let conditions = weather.weather
+        .iter()
+        .map(|condition| condition.description.clone())
+        .collect::<Vec<String>>()
+        .join(", ");
+    let temperature = format!("{:.1} °C ({:.1} °F)",
+                              weather.main.temp,
+                              weather.main.temp * 9.0 / 5.0 + 32.0);
+
+    let colour = random_colour();
+    check_msg(context.send_message(message.channel_id, |m| {
+        m.embed(|e| {
+            let e = e.title(&format!("Weather in {}", place))
+                .colour(colour)
+                .description(&conditions)
+                .field(|f| f.name("Temperature").value(&temperature))
+                .field(|f| f.name("Humidity").value(&format!("{}%", weather.main.humidity)));
+            match weather.wind {
+                Some(ref wind) => {
+                    e.field(|f| f.name("Wind").value(&format!("{:.1} m/s", wind.speed)))
+                },
+                None => e,
+            }
+        })
+    }));
+});
+
+// Queries the current weather at the location, returning `None` if the
+// location could not be found.
+fn query_weather(
+    client: &Client,
+    location: &str,
+    api_key: &str
+) -> Result<Option<WeatherResponse>> {
+    let mut url = WEATHER_URL.clone();
+    url.query_pairs_mut()
+        .clear()
+        .append_pair("q", location)
+        .append_pair("appid", api_key)
+        .append_pair("units", "metric");
+
+    let mut response = client.get(url).send().map_err(Error::from)?;
+    if response.status == StatusCode::NotFound {
+        return Ok(None);
+    }
+
+    let mut result = String::new();
+    response.read_to_string(&mut result).map_err(Error::from)?;
+
+    serde_json::from_str(&result).map(Some).map_err(Error::from)
+}
@@ -0,0 +1,92 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Provides functionality for the `welcome` command, which allows guild admins
+//! to have the bot welcome new members, e.g.
+//! `!welcome set #general Welcome to %guild%, %user%!`.
+
+use serenity::model::ChannelId;
+use util::{check_msg, is_guild_admin, is_guild_channel};
+use welcome::{WELCOMES, Welcome};
+
+/// The maximum number of characters a welcome message may contain, leaving
+/// room for the placeholders to be replaced.
+const MAX_MESSAGE_LENGTH: usize = 1500;
+
+command!(welcome(context, message, args) {
+    let guild_id = match message.guild_id() {
+        Some(guild_id) => guild_id,
+        None => return Err("Welcome messages can only be set in a server.".to_owned()),
+    };
+
+    let mut args = args.into_iter().peekable();
+    let welcome = match args.next().as_ref().map(String::as_ref) {
+        Some("set") => {
+            // Default to the current channel if none is mentioned.
+            let mentioned = match args.peek() {
+                Some(arg) if arg.starts_with("<#") => {
+                    Some(arg.trim_left_matches("<#").trim_right_matches('>').parse::<u64>())
+                },
+                _ => None,
+            };
+            let channel_id = match mentioned {
+                Some(Ok(channel_id)) => {
+                    args.next();
+                    ChannelId(channel_id)
+                },
+                Some(Err(_)) => return Err("Please mention a valid channel.".to_owned()),
+                None => message.channel_id,
+            };
+            if !is_guild_channel(guild_id, channel_id) {
+                return Err("Please mention a channel of this server.".to_owned());
+            }
+
+            let text = args.collect::<Vec<String>>().join(" ");
+            if text.trim().is_empty() {
+                return Err("Please specify a welcome message, e.g. \"Welcome to %guild%, \
+                            %user%!\"."
+                    .to_owned());
+            }
+            if text.chars().count() > MAX_MESSAGE_LENGTH {
+                return Err(format!("Welcome message limit is {} characters",
+                                   MAX_MESSAGE_LENGTH));
+            }
+
+            Some(Welcome {
+                channel_id: channel_id.0,
+                message: text,
+            })
+        },
+        Some("disable") => None,
+        _ => {
+            check_msg(context.say(&match WELCOMES.get(guild_id) {
+                Some(welcome) => {
+                    format!("New members are welcomed in <#{}> with: {}",
+                            welcome.channel_id,
+                            welcome.message)
+                },
+                None => "New members are not welcomed in this server.".to_owned(),
+            }));
+            return Ok(());
+        },
+    };
+
+    if !is_guild_admin(guild_id, message.author.id) {
+        return Err("You do not have permission to do that.".to_owned());
+    }
+
+    let channel_id = welcome.as_ref().map(|welcome| welcome.channel_id);
+    WELCOMES.set(guild_id, welcome)
+        .map_err(|err| format!("Failed to save welcome message: {}", err))?;
+
+    check_msg(context.say(&match channel_id {
+        Some(channel_id) => format!("New members will be welcomed in <#{}>.", channel_id),
+        None => "New members will no longer be welcomed.".to_owned(),
+    }));
+});
@@ -11,13 +11,16 @@
 
 extern crate wolfram_alpha;
 
-use hyper::Client;
+use ::current_config;
 use self::wolfram_alpha::Error as WolframError;
 use self::wolfram_alpha::model::{Pod, QueryResult};
 use serenity::utils::builder::{CreateEmbed, CreateEmbedField};
+use std::collections::VecDeque;
 use std::env;
 use std::error::Error as StdError;
-use util::{check_msg, random_colour, stringify};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use util::{check_msg, http_client, random_colour, retry, with_typing};
 
 lazy_static! {
     static ref PLUGIN: WolframPlugin = {
@@ -27,9 +30,52 @@ lazy_static! {
     };
 }
 
+/// Recently retrieved results, so that repeated queries do not count against
+/// the API's quota. The least recently used result is evicted first.
+struct QueryCache<T> {
+    /// Entries of normalized query, time retrieved and result, from least to
+    /// most recently used.
+    entries: VecDeque<(String, Instant, T)>,
+}
+
+impl<T: Clone> QueryCache<T> {
+    fn new() -> Self {
+        QueryCache { entries: VecDeque::new() }
+    }
+
+    /// Returns the cached result for the query, if it has not expired by
+    /// `now`, marking it as the most recently used.
+    fn get(&mut self, query: &str, now: Instant, ttl: Duration) -> Option<T> {
+        // Expired entries are of no further use, wherever they are.
+        self.entries.retain(|&(_, fetched, _)| now.duration_since(fetched) < ttl);
+
+        let position = match self.entries.iter().position(|&(ref key, _, _)| key == query) {
+            Some(position) => position,
+            None => return None,
+        };
+        let entry = self.entries.remove(position).unwrap();
+        let result = entry.2.clone();
+        self.entries.push_back(entry);
+
+        Some(result)
+    }
+
+    /// Caches the result for the query, evicting the least recently used
+    /// results as needed to stay within `size` entries.
+    fn insert(&mut self, query: String, result: T, now: Instant, size: usize) {
+        self.entries.retain(|&(ref key, _, _)| *key != query);
+        while !self.entries.is_empty() && self.entries.len() >= size {
+            self.entries.pop_front();
+        }
+        if size > 0 {
+            self.entries.push_back((query, now, result));
+        }
+    }
+}
+
 pub struct WolframPlugin {
     app_id: String,
-    hyper_client: Client,
+    cache: Mutex<QueryCache<QueryResult>>,
 }
 
 impl WolframPlugin {
@@ -37,7 +83,7 @@ impl WolframPlugin {
     pub fn new(wolfram_alpha_api_app_id: String) -> Self {
         WolframPlugin {
             app_id: wolfram_alpha_api_app_id,
-            hyper_client: Client::new(),
+            cache: Mutex::new(QueryCache::new()),
         }
     }
 
@@ -48,8 +94,31 @@ impl WolframPlugin {
         };
         trace!("WolframAlpha query: {}", query);
 
-        match wolfram_alpha::query::query(&self.hyper_client, &self.app_id, &query, None) {
-            Ok(query_result) => Ok(query_result),
+        let (ttl, size, attempts, delay) = {
+            let config = current_config();
+            (Duration::from_secs(config.wolfram_cache_ttl),
+             config.wolfram_cache_size,
+             config.http_attempts,
+             config.http_retry_delay)
+        };
+        let key = normalize(&query);
+        if let Some(query_result) = self.cache.lock().unwrap().get(&key, Instant::now(), ttl) {
+            debug!("Using cached WolframAlpha result for: {}", query);
+            return Ok(query_result);
+        }
+
+        let send = || wolfram_alpha::query::query(http_client(), &self.app_id, &query, None);
+        // A response which could not be parsed will not parse any better when
+        // requested again, but other errors are likely connection problems.
+        let is_transient = |why: &WolframError| match *why {
+            WolframError::Xml(_) => false,
+            _ => true,
+        };
+        match retry(send, is_transient, attempts, delay) {
+            Ok(query_result) => {
+                self.cache.lock().unwrap().insert(key, query_result.clone(), Instant::now(), size);
+                Ok(query_result)
+            },
             Err(e) => {
                 let description = match e {
                     WolframError::Xml(_) => "failed to parse response",
@@ -62,60 +131,62 @@ impl WolframPlugin {
 }
 
 command!(wolfram(context, message, args) {
-    context.broadcast_typing(message.channel_id).map_err(stringify)?;
-
-    match PLUGIN.query(&args) {
-        Ok(query_result) => {
-            if query_result.success {
-                // Format the `QueryResult` into Discord-ready output.
-                let colour = random_colour();
-                let pods = query_result.pod
-                    .ok_or_else(|| "Result did not contain any parsable information")?;
-                check_msg(context.send_message(
-                    message.channel_id,
-                    |m| m.embed(|e| format_pods(&pods, e).colour(colour)),
-                ));
-            } else if let Some(didyoumeans) = query_result.didyoumeans {
-                let colour = random_colour();
-                check_msg(context.send_message(message.channel_id, |m| {
-                    m.embed(|e| {
-                        e.title("Query unsuccessful.")
-                            .colour(colour)
-                            .field(|f| {
-                                let field = f.name("Did you mean:");
-
-                                let mut description = String::new();
-                                for item in &didyoumeans.didyoumean {
-                                    description.push_str(&format!("* {}\n", item.value));
-                                }
-                                field.value(description.as_str())
-                            })
-                    })
-                }));
-            } else if let Some(error) = query_result.error {
-                let colour = random_colour();
-                check_msg(context.send_message(message.channel_id, |m| {
-                    m.embed(|e| {
-                        e.title("Wolfram|Alpha returned an error.")
-                            .colour(colour)
-                            .field(|f| {
-                                let field = f.name("Error");
-
-                                let description = format!(
-                                    "Code: {}\nMessage: {}",
-                                    error.code,
-                                    error.msg,
-                                );
-                                field.value(description.as_str())
-                            })
+    let query_result = with_typing(context, message.channel_id, || PLUGIN.query(&args))?;
+
+    if let Some(error) = query_result.error {
+        let colour = random_colour();
+        check_msg(context.send_message(message.channel_id, |m| {
+            m.embed(|e| {
+                e.title("Wolfram|Alpha returned an error.")
+                    .colour(colour)
+                    .field(|f| {
+                        let field = f.name("Error");
+
+                        let description = format!(
+                            "Code: {}\nMessage: {}",
+                            error.code,
+                            error.msg,
+                        );
+                        field.value(description.as_str())
                     })
-                }));
-            } else {
-                check_msg(context.say("Query was unsuccessful. Perhaps try rewording it?"));
-            }
-        },
-        Err(err) => check_msg(context.say(err.as_ref())),
+            })
+        }));
+        return Ok(());
     }
+
+    let pods = match query_result.pod {
+        Some(ref pods) if query_result.success && !pods.is_empty() => pods,
+        // Wolfram|Alpha could not make sense of the query, but may have
+        // suggested how it could be reworded.
+        _ => {
+            let suggestions = query_result.didyoumeans
+                .map(|didyoumeans| didyoumeans.didyoumean)
+                .unwrap_or_else(Vec::new);
+            let colour = random_colour();
+            check_msg(context.send_message(message.channel_id, |m| {
+                m.embed(|e| {
+                    let e = e.title("Wolfram|Alpha couldn't interpret that.").colour(colour);
+                    if suggestions.is_empty() {
+                        return e.description("Perhaps try rewording it?");
+                    }
+
+                    let mut description = String::new();
+                    for item in &suggestions {
+                        description.push_str(&format!("* {}\n", item.value));
+                    }
+                    e.field(|f| f.name("Did you mean:").value(description.as_str()))
+                })
+            }));
+            return Ok(());
+        },
+    };
+
+    // Format the `QueryResult` into Discord-ready output.
+    let colour = random_colour();
+    check_msg(context.send_message(
+        message.channel_id,
+        |m| m.embed(|e| format_pods(pods, e).colour(colour)),
+    ));
 });
 
 fn format_pods(pods: &[Pod], embed: CreateEmbed) -> CreateEmbed {
@@ -192,6 +263,12 @@ fn format_pod(pod: &Pod, f: CreateEmbedField) -> CreateEmbedField {
     f.value(result.as_ref())
 }
 
+/// Returns the form of a query used to look it up in the cache, so that
+/// queries differing only in case or spacing share a result.
+fn normalize(query: &str) -> String {
+    query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
 #[inline]
 fn unescape(s: &str) -> String {
     s.replace("&amp;", "&")
@@ -200,3 +277,48 @@ fn unescape(s: &str) -> String {
         .replace("&quot;", "\"")
         .replace("&apos;", "\'")
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use super::QueryCache;
+
+    const SIZE: usize = 2;
+
+    fn ttl() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    #[test]
+    fn cache_hit_within_ttl() {
+        let now = Instant::now();
+        let mut cache = QueryCache::new();
+        cache.insert("pi".to_owned(), "3.14159".to_owned(), now, SIZE);
+        let later = now + ttl() - Duration::from_secs(1);
+        assert_eq!(cache.get("pi", later, ttl()), Some("3.14159".to_owned()));
+    }
+
+    #[test]
+    fn cache_miss_once_expired() {
+        let now = Instant::now();
+        let mut cache = QueryCache::new();
+        cache.insert("pi".to_owned(), "3.14159".to_owned(), now, SIZE);
+        assert_eq!(cache.get("pi", now + ttl(), ttl()), None);
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let now = Instant::now();
+        let mut cache = QueryCache::new();
+        cache.insert("pi".to_owned(), "3.14159".to_owned(), now, SIZE);
+        cache.insert("e".to_owned(), "2.71828".to_owned(), now, SIZE);
+        // Using "pi" leaves "e" as the least recently used.
+        assert!(cache.get("pi", now, ttl()).is_some());
+        cache.insert("phi".to_owned(), "1.61803".to_owned(), now, SIZE);
+
+        assert_eq!(cache.entries.len(), SIZE);
+        assert_eq!(cache.get("e", now, ttl()), None);
+        assert!(cache.get("pi", now, ttl()).is_some());
+        assert!(cache.get("phi", now, ttl()).is_some());
+    }
+}
@@ -12,23 +12,39 @@
 extern crate regex;
 extern crate xkcd;
 
+use ::current_config;
 use error::{Error, Result};
 use hyper::Url;
-use hyper::client::Client;
+use hyper::client::{Client, Response};
+use rand::{self, Rng};
 use self::regex::Regex;
+use self::xkcd::model::XkcdResponse;
 use serde_json;
 use std::env;
 use std::io::Read;
-use util::{check_msg, split_list};
+use std::result::Result as StdResult;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use util::{check_msg, get_with_retry, http_client, random_colour, split_list, with_typing};
+
+/// How long the comic index is cached for before it is fetched again.
+const INDEX_MAX_AGE: u64 = 6 * 60 * 60;
+/// The number of the comic which was deliberately never published.
+const MISSING_COMIC: u32 = 404;
+/// The maximum number of matching comics listed for a search.
+const MAX_RESULTS: usize = 5;
 
 lazy_static! {
+    static ref ARCHIVE_REGEX: Regex =
+        Regex::new(r#"<a href="/(\d+)/" title="[^"]*">([^<]*)</a>"#).unwrap();
+    static ref ARCHIVE_URL: Url = "https://xkcd.com/archive/".parse::<Url>().unwrap();
     static ref GOOGLE_CSE_URL: Url = "https://www.googleapis.com/customsearch/v1".parse::<Url>()
         .unwrap();
+    static ref LATEST_URL: Url = "https://xkcd.com/info.0.json".parse::<Url>().unwrap();
+    static ref INDEX: Mutex<Option<ComicIndex>> = Mutex::new(None);
     static ref PLUGIN: XkcdPlugin = {
-        let cse_api_key = env::var("GOOGLE_XKCD_CUSTOM_SEARCH_API_KEY")
-            .expect("GOOGLE_XKCD_CUSTOM_SEARCH_API_KEY env var not set.");
-        let cse_engine_id = env::var("GOOGLE_XKCD_CUSTOM_SEARCH_ENGINE_ID")
-            .expect("GOOGLE_XKCD_CUSTOM_SEARCH_ENGINE_ID env var not set.");
+        let cse_api_key = env::var("GOOGLE_XKCD_CUSTOM_SEARCH_API_KEY").ok();
+        let cse_engine_id = env::var("GOOGLE_XKCD_CUSTOM_SEARCH_ENGINE_ID").ok();
         XkcdPlugin::new(cse_api_key, cse_engine_id)
     };
     static ref XKCD_URL_REGEX: Regex = Regex::new(r"^https://xkcd.com/(\d*)").unwrap();
@@ -40,97 +56,210 @@ include!("xkcd.in.rs");
 #[cfg(feature = "with-syntex")]
 include!(concat!(env!("OUT_DIR"), "/xkcd.rs"));
 
+/// The number and title of every comic, as listed in the XKCD archive.
+struct ComicIndex {
+    comics: Vec<(u32, String)>,
+    fetched: Instant,
+}
+
+impl ComicIndex {
+    fn is_stale(&self) -> bool {
+        self.fetched.elapsed() > Duration::from_secs(INDEX_MAX_AGE)
+    }
+
+    /// Returns the comics whose title contains the query, ignoring case.
+    fn matching(&self, query: &str) -> Vec<(u32, String)> {
+        let query = query.to_lowercase();
+        self.comics
+            .iter()
+            .filter(|&&(_, ref title)| title.to_lowercase().contains(&query))
+            .cloned()
+            .collect()
+    }
+}
+
+/// A response to the xkcd command.
+enum Reply {
+    Comic(XkcdResponse),
+    Text(String),
+}
+
 struct XkcdPlugin {
-    hyper_client: Client,
-    google_custom_search_api_key: String,
-    google_custom_search_engine_id: String,
+    google_custom_search_api_key: Option<String>,
+    google_custom_search_engine_id: Option<String>,
 }
 
 impl XkcdPlugin {
     /// Returns a new instance of `XkcdPlugin`.
-    fn new(google_custom_search_api_key: String, google_custom_search_engine_id: String) -> Self {
+    fn new(
+        google_custom_search_api_key: Option<String>,
+        google_custom_search_engine_id: Option<String>
+    ) -> Self {
         XkcdPlugin {
-            hyper_client: Client::new(),
             google_custom_search_api_key: google_custom_search_api_key,
             google_custom_search_engine_id: google_custom_search_engine_id,
         }
     }
 
-    fn random(&self) -> String {
-        debug!("Searching for random comic");
-        xkcd::random::random(&self.hyper_client)
-            .ok()
-            .map(|comic| comic.img.into_string())
-            .unwrap_or("Failed to retrieve random comic".to_owned())
+    fn random(&self) -> StdResult<XkcdResponse, String> {
+        debug!("Retrieving random comic");
+        let latest = try!(self.latest_comic());
+        let mut rng = rand::thread_rng();
+        loop {
+            let id = rng.gen_range(1, latest.num + 1);
+            // There is deliberately no comic #404.
+            if id != MISSING_COMIC {
+                return self.comic(id);
+            }
+        }
     }
 
-    fn search(&self, args: &[String]) -> String {
+    fn search(&self, args: &[String]) -> StdResult<Reply, String> {
         debug!("Searching for comic");
         let query: String = match args.len() {
-            0 => return "Missing comic search query".to_owned(),
+            0 => return Err("Missing comic search query".to_owned()),
             _ => args.join(" "),
         };
         trace!("Query: {}", query);
 
-        match query_cse(&self.hyper_client,
-                        &query,
-                        &self.google_custom_search_api_key,
-                        &self.google_custom_search_engine_id) {
+        let matches = match self.title_matches(&query) {
+            Ok(matches) => matches,
+            Err(why) => {
+                warn!("Failed to retrieve XKCD comic index: {}", why);
+                return Err("Failed to retrieve the XKCD archive, try again later".to_owned());
+            },
+        };
+        match matches.len() {
+            0 => {},
+            1 => return self.comic(matches[0].0).map(Reply::Comic),
+            _ => {
+                let mut lines = matches.iter()
+                    .take(MAX_RESULTS)
+                    .map(|&(id, ref title)| {
+                        format!("{}: {} <https://xkcd.com/{}/>", id, title, id)
+                    })
+                    .collect::<Vec<_>>();
+                if matches.len() > MAX_RESULTS {
+                    lines.push(format!("...and {} more", matches.len() - MAX_RESULTS));
+                }
+                return Ok(Reply::Text(lines.join("\n")));
+            },
+        }
+
+        // Fall back to a full-text search when no title matches, if one has
+        // been configured.
+        let (api_key, engine_id) = match (self.google_custom_search_api_key.as_ref(),
+                                          self.google_custom_search_engine_id.as_ref()) {
+            (Some(api_key), Some(engine_id)) => (api_key, engine_id),
+            _ => return Err(format!("No comic found with a title matching \"{}\"", query)),
+        };
+        match query_cse(http_client(), &query, api_key, engine_id) {
             Ok(res) => {
-                let mut comic_urls = res.items
+                let mut comic_ids = res.items
                     .iter()
                     .filter_map(|item| XKCD_URL_REGEX.captures_iter(&item.link).next())
-                    .filter_map(|capture| capture.at(1));
-                match comic_urls.next() {
-                    Some(comic_id_str) => comic_id_str.parse::<u32>()
-                        .ok()
-                        .map(|id| {
-                            xkcd::comics::get(&self.hyper_client, id)
-                                .ok()
-                                .map(|comic| comic.img.into_string())
-                                .unwrap_or_else(|| format!("Failed to retrieve comic: {}", id))
-                        })
-                        .unwrap_or_else(|| format!("Failed to retrieve comic: {}", comic_id_str)),
-                    None => "No results in query".to_owned(),
+                    .filter_map(|capture| capture.at(1))
+                    .filter_map(|id| id.parse::<u32>().ok());
+                match comic_ids.next() {
+                    Some(id) => self.comic(id).map(Reply::Comic),
+                    None => Err("No results in query".to_owned()),
                 }
             },
-            Err(_) => "No matching comic found".to_owned(),
+            Err(_) => Err("No matching comic found".to_owned()),
         }
     }
 
-    fn latest_comic(&self) -> String {
-        debug!("Retrieving latest comic");
-        match xkcd::comics::latest(&self.hyper_client) {
-            Ok(comic) => comic.img.into_string(),
-            Err(_) => "Failed to retrieve latest comic".to_owned(),
+    /// Returns the comics whose title matches the query, fetching the comic
+    /// index if it has not been cached or has gone stale.
+    fn title_matches(&self, query: &str) -> Result<Vec<(u32, String)>> {
+        let mut index = INDEX.lock().unwrap();
+        let stale = index.as_ref().map_or(true, ComicIndex::is_stale);
+        if stale {
+            match fetch_index(http_client()) {
+                Ok(fetched) => *index = Some(fetched),
+                // A stale index is still better than none at all.
+                Err(why) => {
+                    if index.is_none() {
+                        return Err(why);
+                    }
+                    warn!("Failed to refresh XKCD comic index, using cached copy: {}", why);
+                },
+            }
+        }
+
+        Ok(index.as_ref().map(|index| index.matching(query)).unwrap_or_else(Vec::new))
+    }
+
+    fn comic(&self, id: u32) -> StdResult<XkcdResponse, String> {
+        debug!("Retrieving comic {}", id);
+        fetch_comic(http_client(), Some(id)).map_err(|why| {
+            warn!("Failed to retrieve XKCD comic {}: {}", id, why);
+            format!("Failed to retrieve comic: {}", id)
+        })
+    }
+
+    /// Retrieves the specified comic, checking first that it has been
+    /// published.
+    fn numbered_comic(&self, id: u32) -> StdResult<XkcdResponse, String> {
+        let latest = try!(self.latest_comic());
+        if id == latest.num {
+            return Ok(latest);
         }
+        if id == 0 || id > latest.num {
+            return Err(format!("There is no comic {}, the latest is {}", id, latest.num));
+        }
+        if id == MISSING_COMIC {
+            return Err(format!("Comic {} not found", id));
+        }
+
+        self.comic(id)
+    }
+
+    fn latest_comic(&self) -> StdResult<XkcdResponse, String> {
+        debug!("Retrieving latest comic");
+        fetch_comic(http_client(), None).map_err(|why| {
+            warn!("Failed to retrieve latest XKCD comic: {}", why);
+            "Failed to retrieve latest comic".to_owned()
+        })
     }
 }
 
-command!(xkcd(context, _message, args) {
+command!(xkcd(context, message, args) {
     let (command, args) = split_list(args);
 
-    let response = match command.as_ref().map(String::as_ref) {
-        Some("search") => PLUGIN.search(&args),
-        Some("random") => PLUGIN.random(),
-        Some(comic_id) => {
-            match comic_id.parse() {
-                Ok(comic_id) => {
-                    match xkcd::comics::get(&PLUGIN.hyper_client, comic_id) {
-                        Ok(comic) => comic.img.into_string(),
-                        Err(_) => format!("Failed to retrieve comic: {}", comic_id),
-                    }
-                },
-                _ => {
-                    "Please provide a valid argument (\"search\", \"random\", or a comic ID)"
-                        .to_owned()
-                },
-            }
-        },
-        _ => PLUGIN.latest_comic(),
-    };
+    let reply = with_typing(&context, message.channel_id, || {
+        match command.as_ref().map(String::as_ref) {
+            Some("search") => PLUGIN.search(&args),
+            Some("random") => PLUGIN.random().map(Reply::Comic),
+            Some(comic_id) => {
+                match comic_id.parse() {
+                    Ok(comic_id) => PLUGIN.numbered_comic(comic_id).map(Reply::Comic),
+                    _ => {
+                        Err("Please provide a valid argument (\"search\", \"random\", or a comic \
+                             number)"
+                            .to_owned())
+                    },
+                }
+            },
+            None => PLUGIN.latest_comic().map(Reply::Comic),
+        }
+    });
 
-    check_msg(context.say(response.as_ref()));
+    match try!(reply) {
+        Reply::Comic(comic) => {
+            let url = format!("https://xkcd.com/{}/", comic.num);
+            check_msg(context.send_message(message.channel_id, |m| {
+                m.embed(|e| {
+                    e.title(&format!("{}: {}", comic.num, comic.safe_title))
+                        .url(&url)
+                        .colour(random_colour())
+                        .image(|i| i.url(comic.img.as_str()))
+                        .footer(|f| f.text(&comic.alt))
+                })
+            }));
+        },
+        Reply::Text(text) => check_msg(context.say(&text)),
+    }
 });
 
 fn query_cse(
@@ -146,9 +275,66 @@ fn query_cse(
         .append_pair("cx", search_engine_id)
         .append_pair("q", query);
 
-    let mut response = try!(client.get(url).send().map_err(Error::from));
+    let mut response = try!(get(client, &url));
     let mut result = String::new();
     try!(response.read_to_string(&mut result).map_err(Error::from));
 
     serde_json::from_str(&result).map_err(Error::from)
 }
+
+/// Sends a GET request to the URL, retrying as configured.
+fn get(client: &Client, url: &Url) -> Result<Response> {
+    let (attempts, delay) = {
+        let config = current_config();
+        (config.http_attempts, config.http_retry_delay)
+    };
+
+    get_with_retry(client, url, attempts, delay)
+}
+
+/// Retrieves the comic with the specified number, or the latest comic if no
+/// number is specified.
+fn fetch_comic(client: &Client, id: Option<u32>) -> Result<XkcdResponse> {
+    let url = match id {
+        Some(id) => try!(format!("https://xkcd.com/{}/info.0.json", id).parse::<Url>()),
+        None => LATEST_URL.clone(),
+    };
+    let mut response = try!(get(client, &url));
+    let mut result = String::new();
+    try!(response.read_to_string(&mut result).map_err(Error::from));
+
+    serde_json::from_str(&result).map_err(Error::from)
+}
+
+/// Retrieves the number and title of every comic from the XKCD archive.
+fn fetch_index(client: &Client) -> Result<ComicIndex> {
+    let mut response = try!(get(client, &ARCHIVE_URL));
+    let mut result = String::new();
+    try!(response.read_to_string(&mut result).map_err(Error::from));
+
+    let comics = ARCHIVE_REGEX.captures_iter(&result)
+        .filter_map(|capture| {
+            let id = capture.at(1).and_then(|id| id.parse::<u32>().ok());
+            let title = capture.at(2).map(unescape_html);
+            match (id, title) {
+                (Some(id), Some(title)) => Some((id, title)),
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    debug!("Fetched XKCD comic index with {} comics", comics.len());
+
+    Ok(ComicIndex {
+        comics: comics,
+        fetched: Instant::now(),
+    })
+}
+
+/// Replaces the HTML entities which appear in archive titles.
+fn unescape_html(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
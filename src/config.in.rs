@@ -1,11 +1,125 @@
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Config {
+    /// The layout version of the config file. Files without one predate
+    /// versioning.
+    #[serde(default)]
+    pub version: u32,
     /// Name by which the bot is referred to internally (e.g. in debug output).
     pub bot_name: String,
     /// The command prefix for the bot.
     pub command_prefix: String,
+    /// Whether commands may also be invoked by mentioning the bot in place of
+    /// the prefix, e.g. "@smexybot roll 2d6". Disabled by default.
+    #[serde(default)]
+    pub respond_to_mention: bool,
     /// The URL for the source of the project.
     pub source_url: String,
     /// The authors to use in author checks for permissions.
     pub owners: HashSet<u64>,
+    /// The directory in which all persisted state, e.g. tags, is stored.
+    /// Defaults to the working directory.
+    #[serde(default = "default_data_dir")]
+    pub data_dir: String,
+    /// The name of the timezone in which times are displayed, e.g.
+    /// "Europe/London". Defaults to UTC.
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// The maximum number of dice which may be rolled in a single roll.
+    #[serde(default = "default_max_dice")]
+    pub max_dice: u32,
+    /// The maximum number of sides a die may have in a roll.
+    #[serde(default = "default_max_die_sides")]
+    pub max_die_sides: u32,
+    /// Whether the result of a single roll is preceded by what was rolled,
+    /// e.g. "d20: 17" rather than "17".
+    #[serde(default)]
+    pub verbose_rolls: bool,
+    /// The maximum number of coins which may be flipped at once.
+    #[serde(default = "default_max_flips")]
+    pub max_flips: u32,
+    /// The maximum number of characters a tag's content may contain.
+    #[serde(default = "default_max_tag_content_length")]
+    pub max_tag_content_length: usize,
+    /// The order of the Markov chain used by the `fuyu` command. Higher orders
+    /// produce more coherent, but less random, output.
+    #[serde(default = "default_fuyu_order")]
+    pub fuyu_order: usize,
+    /// The maximum number of characters in a message generated by the `fuyu`
+    /// command. Longer messages are truncated.
+    #[serde(default = "default_fuyu_max_len")]
+    pub fuyu_max_len: usize,
+    /// Whether the `ping` command may only be used by the bot's owners.
+    #[serde(default = "default_ping_owners_only")]
+    pub ping_owners_only: bool,
+    /// Whether to tell users when they try to use a command disabled in the
+    /// server, rather than silently ignoring them.
+    #[serde(default)]
+    pub disabled_command_reply: bool,
+    /// The ID of the channel in which the bot announces joining or leaving a
+    /// guild, if any.
+    #[serde(default)]
+    pub guild_log_channel: Option<u64>,
+    /// The file to which failed commands are logged, if any. Relative paths
+    /// are relative to `data_dir`.
+    #[serde(default = "default_error_log_path")]
+    pub error_log_path: Option<String>,
+    /// The size, in bytes, past which the error log is moved to
+    /// `<error_log_path>.1` and a new one started.
+    #[serde(default = "default_error_log_max_size")]
+    pub error_log_max_size: u64,
+    /// Alternative names for commands, mapped to the names of the commands
+    /// they stand for, e.g. "r": "roll". An alias with the same name as a
+    /// command is ignored, as the command takes precedence.
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    /// The number of seconds each user must wait between uses of a command,
+    /// keyed by the name of the command.
+    #[serde(default)]
+    pub cooldowns: HashMap<String, u64>,
+    /// The reply sent when a command is used again before its cooldown has
+    /// passed. `%time%` is replaced with the number of seconds remaining.
+    #[serde(default = "default_rate_limit_message")]
+    pub rate_limit_message: String,
+    /// The Open Exchange Rates app ID used by the `convert` command.
+    #[serde(default)]
+    pub currency_api_key: Option<String>,
+    /// The OpenWeatherMap API key used by the `weather` command.
+    #[serde(default)]
+    pub weather_api_key: Option<String>,
+    /// The number of times to try an HTTP request to an external API before
+    /// giving up, if it fails due to a connection or server error.
+    #[serde(default = "default_http_attempts")]
+    pub http_attempts: u32,
+    /// The number of milliseconds to wait before retrying a failed HTTP
+    /// request. The delay doubles with each failed attempt.
+    #[serde(default = "default_http_retry_delay")]
+    pub http_retry_delay: u64,
+    /// The number of seconds a Wolfram|Alpha result is reused for when the
+    /// same query is repeated.
+    #[serde(default = "default_wolfram_cache_ttl")]
+    pub wolfram_cache_ttl: u64,
+    /// The maximum number of Wolfram|Alpha results kept for reuse. If zero,
+    /// results are not cached.
+    #[serde(default = "default_wolfram_cache_size")]
+    pub wolfram_cache_size: usize,
+    /// The statuses the bot cycles through, e.g. "with %guilds% servers".
+    /// `%guilds%` is replaced with the number of servers the bot is in. If
+    /// empty, no status is set.
+    #[serde(default)]
+    pub statuses: Vec<String>,
+    /// The number of seconds each status is shown for.
+    #[serde(default = "default_status_interval")]
+    pub status_interval: u64,
+    /// The number of times to try starting the client before giving up.
+    #[serde(default = "default_max_start_attempts")]
+    pub max_start_attempts: u32,
+    /// The number of seconds to wait before retrying to start the client. The
+    /// delay doubles with each failed attempt.
+    #[serde(default = "default_start_retry_delay")]
+    pub start_retry_delay: u64,
+    /// The number of shards to start. If not set, the number of shards
+    /// recommended by Discord is used. Overridden by the `SMEXYBOT_SHARDS`
+    /// environment variable.
+    #[serde(default)]
+    pub shard_count: Option<u64>,
 }
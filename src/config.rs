@@ -7,11 +7,71 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use error::Result;
-use serde_json;
-use std::collections::HashSet;
+use chrono_tz::Tz;
+use error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{self, Value};
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::{ErrorKind, Read};
+use std::path::Path;
+use toml;
+use util::write_atomically;
+
+/// The layout version of config files written by this version of the bot.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// The formats in which the config file may be written.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Format {
+    Json,
+    Toml,
+}
+
+impl Format {
+    // Files ending in ".toml" are read as TOML, and any others as JSON.
+    fn of(name: &str) -> Format {
+        match Path::new(name).extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+
+    fn parse(&self, contents: &str) -> Result<Value> {
+        match *self {
+            Format::Json => Ok(serde_json::from_str(contents)?),
+            Format::Toml => {
+                let mut parser = toml::Parser::new(contents);
+                let table = match parser.parse() {
+                    Some(table) => table,
+                    None => {
+                        let errors = parser.errors
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect::<Vec<_>>();
+                        return Err(Error::Toml(errors.join(", ")));
+                    },
+                };
+
+                // Converting the TOML to JSON first means that both formats
+                // are migrated and checked in the same way.
+                let mut decoder = toml::Decoder::new(toml::Value::Table(table));
+                Value::deserialize(&mut decoder).map_err(|err| Error::Toml(err.to_string()))
+            },
+        }
+    }
+
+    fn serialize(&self, config: &Config) -> Result<String> {
+        match *self {
+            Format::Json => Ok(serde_json::to_string_pretty(config)?),
+            Format::Toml => {
+                let mut encoder = toml::Encoder::new();
+                config.serialize(&mut encoder).map_err(|err| Error::Toml(err.to_string()))?;
+                Ok(toml::Value::Table(encoder.toml).to_string())
+            },
+        }
+    }
+}
 
 #[cfg(feature = "nightly")]
 include!("config.in.rs");
@@ -35,29 +95,231 @@ impl Config {
     }
 
     pub fn load_from_file(name: &str) -> Result<Self> {
+        let (config, outdated) = Config::read(name)?;
+        info!("Loaded config from: \"{}\"", name);
+        config.validate();
+
+        // Rewrite the config in the current layout, so that it only has to be
+        // migrated once. The config is still usable if this fails.
+        if outdated {
+            match config.save(name) {
+                Ok(()) => info!("Migrated config \"{}\" to version {}", name, CONFIG_VERSION),
+                Err(err) => warn!("Failed to save migrated config to \"{}\": {}", name, err),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Reads the config from a file, along with whether it was in an older
+    /// layout. Unlike `load_from_file`, the file is never rewritten and no
+    /// problems are reported.
+    pub fn read(name: &str) -> Result<(Self, bool)> {
         let mut file = match File::open(name) {
             Ok(file) => file,
             // If no file is present, assume this is a fresh config.
-            Err(ref err) if err.kind() == ErrorKind::NotFound => return Ok(Default::default()),
-            Err(_) => panic!("Failed to open file: {}", name),
+            Err(ref err) if err.kind() == ErrorKind::NotFound => {
+                return Ok((Default::default(), false))
+            },
+            Err(err) => return Err(err.into()),
         };
         let mut config = String::new();
-        file.read_to_string(&mut config)
-            .expect(&format!("Failed to read from file: {}", name));
-        let config = serde_json::from_str(&config).expect("Failed to deserialize Config");
-        info!("Loaded config from: \"{}\"", name);
+        file.read_to_string(&mut config)?;
+        let mut value = Format::of(name).parse(&config)?;
 
-        Ok(config)
+        let version = value.find("version").and_then(Value::as_u64).unwrap_or(0);
+        let outdated = version < u64::from(CONFIG_VERSION);
+        if outdated {
+            migrate(&mut value);
+        }
+
+        Ok((serde_json::from_value(value)?, outdated))
+    }
+
+    // Saves the config in the format its file name suggests.
+    fn save(&self, name: &str) -> Result<()> {
+        let config = Format::of(name).serialize(self)?;
+        write_atomically(name, config.as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Returns a description of each value which is invalid, and so is
+    /// ignored or replaced when used.
+    pub fn errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.command_prefix.is_empty() {
+            errors.push("The command prefix is empty".to_owned());
+        }
+        // Discord IDs begin with the time they were created at, which is
+        // never zero.
+        for owner in self.owners.iter().filter(|&&owner| owner >> 22 == 0) {
+            errors.push(format!("Owner {} is not a valid user ID", owner));
+        }
+        if let Some(ref timezone) = self.timezone {
+            if timezone.parse::<Tz>().is_err() {
+                errors.push(format!("Unknown timezone \"{}\", UTC will be used instead",
+                                    timezone));
+            }
+        }
+
+        errors
+    }
+
+    /// Returns a description of each value which is accepted, but likely to
+    /// be a mistake.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if self.version > CONFIG_VERSION {
+            warnings.push(format!("The config is version {}, but only version {} is supported",
+                                  self.version,
+                                  CONFIG_VERSION));
+        }
+        if self.owners.is_empty() {
+            warnings.push("There are no owners, so no one can use owner-only commands"
+                .to_owned());
+        }
+        if !self.rate_limit_message.contains("%time%") {
+            warnings.push("The rate limit message does not contain \"%time%\", so users will \
+                           not be told how long to wait"
+                .to_owned());
+        }
+
+        warnings
+    }
+
+    // Logs any problems with the config, which is used as-is regardless.
+    fn validate(&self) {
+        for problem in self.errors().iter().chain(&self.warnings()) {
+            warn!("{}", problem);
+        }
     }
 }
 
 impl Default for Config {
     fn default() -> Config {
         Config {
+            version: CONFIG_VERSION,
             bot_name: "smexybot".to_owned(),
             command_prefix: ";".to_owned(),
             owners: HashSet::new(),
+            respond_to_mention: false,
             source_url: "https://github.com/indiv0/smexybot".to_owned(),
+            data_dir: default_data_dir(),
+            timezone: None,
+            max_dice: default_max_dice(),
+            max_die_sides: default_max_die_sides(),
+            verbose_rolls: false,
+            max_flips: default_max_flips(),
+            max_tag_content_length: default_max_tag_content_length(),
+            fuyu_order: default_fuyu_order(),
+            fuyu_max_len: default_fuyu_max_len(),
+            ping_owners_only: default_ping_owners_only(),
+            disabled_command_reply: false,
+            guild_log_channel: None,
+            error_log_path: default_error_log_path(),
+            error_log_max_size: default_error_log_max_size(),
+            aliases: HashMap::new(),
+            cooldowns: HashMap::new(),
+            rate_limit_message: default_rate_limit_message(),
+            currency_api_key: None,
+            weather_api_key: None,
+            http_attempts: default_http_attempts(),
+            http_retry_delay: default_http_retry_delay(),
+            wolfram_cache_ttl: default_wolfram_cache_ttl(),
+            wolfram_cache_size: default_wolfram_cache_size(),
+            statuses: Vec::new(),
+            status_interval: default_status_interval(),
+            max_start_attempts: default_max_start_attempts(),
+            start_retry_delay: default_start_retry_delay(),
+            shard_count: None,
         }
     }
 }
+
+// Upgrades a config from an older layout to the current one. Fields added
+// since are given their defaults when the config is deserialized, so only
+// fields which were renamed or changed in meaning need to be handled here.
+fn migrate(config: &mut Value) {
+    if let Some(config) = config.as_object_mut() {
+        // Version 1 introduced the version itself, and nothing else.
+        config.insert("version".to_owned(), Value::U64(u64::from(CONFIG_VERSION)));
+    }
+}
+
+fn default_data_dir() -> String {
+    ".".to_owned()
+}
+
+fn default_max_dice() -> u32 {
+    1000
+}
+
+fn default_max_die_sides() -> u32 {
+    1000
+}
+
+fn default_max_flips() -> u32 {
+    1000
+}
+
+fn default_fuyu_max_len() -> usize {
+    // Discord's embed description limit is 2048 characters.
+    2000
+}
+
+fn default_fuyu_order() -> usize {
+    1
+}
+
+fn default_ping_owners_only() -> bool {
+    true
+}
+
+fn default_error_log_path() -> Option<String> {
+    Some("errors.jsonl".to_owned())
+}
+
+fn default_error_log_max_size() -> u64 {
+    // 10 MiB.
+    10 * 1024 * 1024
+}
+
+fn default_rate_limit_message() -> String {
+    "Try this again in %time% seconds.".to_owned()
+}
+
+fn default_http_attempts() -> u32 {
+    3
+}
+
+fn default_http_retry_delay() -> u64 {
+    500
+}
+
+fn default_wolfram_cache_ttl() -> u64 {
+    10 * 60
+}
+
+fn default_wolfram_cache_size() -> usize {
+    32
+}
+
+fn default_status_interval() -> u64 {
+    60
+}
+
+fn default_max_start_attempts() -> u32 {
+    5
+}
+
+fn default_start_retry_delay() -> u64 {
+    1
+}
+
+fn default_max_tag_content_length() -> usize {
+    // Discord's message length limit.
+    2000
+}
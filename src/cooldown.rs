@@ -0,0 +1,50 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Tracks when each user last ran each command, so that commands can be given
+//! a cooldown via the `cooldowns` option of the `Config`.
+
+use chrono::{DateTime, Duration, UTC};
+use serenity::model::UserId;
+use std::collections::HashMap;
+use typemap::Key;
+
+pub struct CommandCooldowns;
+
+impl Key for CommandCooldowns {
+    type Value = Cooldowns;
+}
+
+/// The last time each user ran each command with a cooldown.
+#[derive(Debug, Default)]
+pub struct Cooldowns {
+    last_run: HashMap<(String, UserId), DateTime<UTC>>,
+}
+
+impl Cooldowns {
+    /// Records an invocation of `command` by `user_id`, unless the user last
+    /// ran it less than `cooldown` seconds ago, in which case the time left
+    /// until they may run it again is returned instead.
+    pub fn check(&mut self, command: &str, user_id: UserId, cooldown: u64) -> Option<Duration> {
+        let now = UTC::now();
+        let key = (command.to_owned(), user_id);
+        #[cfg_attr(feature = "clippy", allow(cast_possible_wrap))]
+        let cooldown = Duration::seconds(cooldown as i64);
+
+        if let Some(last_run) = self.last_run.get(&key) {
+            let elapsed = now - *last_run;
+            if elapsed < cooldown {
+                return Some(cooldown - elapsed);
+            }
+        }
+
+        self.last_run.insert(key, now);
+        None
+    }
+}
@@ -0,0 +1,16 @@
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CommandCounts {
+    /// The number of times each command has been run.
+    pub commands: HashMap<String, u64>,
+    /// The number of times each user has run each command, keyed by command
+    /// name and then by user ID.
+    #[serde(default)]
+    pub users: HashMap<String, HashMap<String, u64>>,
+    /// When the counts began accumulating.
+    pub since: DateTime<UTC>,
+    /// When the counts were last persisted to disk.
+    pub last_saved: Option<DateTime<UTC>>,
+    /// Whether the counts have changed since they were last persisted.
+    #[serde(skip_serializing, skip_deserializing)]
+    dirty: bool,
+}
@@ -7,13 +7,153 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-extern crate typemap;
-
-use self::typemap::Key;
+use chrono::{DateTime, UTC};
+use error::Result;
+use serde_json;
+use serenity::model::UserId;
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use typemap::{Key, ShareMap};
+use util::write_atomically;
+
+/// The number of seconds between saves of changed counts.
+const SAVE_INTERVAL: u64 = 30;
+
+#[cfg(feature = "nightly")]
+include!("counter.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/counter.rs"));
 
 pub struct CommandCounter;
 
 impl Key for CommandCounter {
-    type Value = HashMap<String, u64>;
+    type Value = CommandCounts;
+}
+
+impl CommandCounts {
+    /// Loads the counts persisted at `name`, starting fresh if the file is
+    /// missing or cannot be parsed.
+    pub fn load(name: &str) -> Self {
+        match CommandCounts::load_from_file(name) {
+            Ok(counts) => {
+                info!("Loaded command counts from: \"{}\"", name);
+                counts
+            },
+            Err(err) => {
+                warn!("Failed to load command counts from \"{}\": {}", name, err);
+                Default::default()
+            },
+        }
+    }
+
+    fn load_from_file(name: &str) -> Result<Self> {
+        let mut file = File::open(name)?;
+        let mut counts = String::new();
+        file.read_to_string(&mut counts)?;
+
+        serde_json::from_str(&counts).map_err(From::from)
+    }
+
+    /// Records a single invocation of `command` by `user_id`.
+    pub fn increment(&mut self, command: &str, user_id: UserId) {
+        *self.commands.entry(command.to_owned()).or_insert(0) += 1;
+        *self.users
+            .entry(command.to_owned())
+            .or_insert_with(HashMap::new)
+            .entry(user_id.0.to_string())
+            .or_insert(0) += 1;
+        self.dirty = true;
+    }
+
+    /// Returns the commands run by `user_id`, along with the number of times
+    /// each was run, sorted from most to least used.
+    pub fn user_counts(&self, user_id: UserId) -> Vec<(String, u64)> {
+        let user_id = user_id.0.to_string();
+        let mut counts = self.users
+            .iter()
+            .filter_map(|(command, users)| users.get(&user_id).map(|c| (command.clone(), *c)))
+            .collect::<Vec<(String, u64)>>();
+        counts.sort_by(|a, b| (b.1, &a.0).cmp(&(a.1, &b.0)));
+        counts
+    }
+
+    /// Persists the counts to `name`, updating the last-saved timestamp.
+    pub fn save(&mut self, name: &str) -> Result<()> {
+        self.last_saved = Some(UTC::now());
+        let counts = serde_json::to_string(self)?;
+        write(name, &counts)?;
+        self.dirty = false;
+
+        Ok(())
+    }
+
+    // Serializes the counts if they have changed since they were last
+    // persisted, so that they can be written once the lock on them has been
+    // released.
+    fn take_unsaved(&mut self) -> Result<Option<String>> {
+        if !self.dirty {
+            return Ok(None);
+        }
+
+        self.last_saved = Some(UTC::now());
+        let counts = serde_json::to_string(self)?;
+        self.dirty = false;
+
+        Ok(Some(counts))
+    }
+}
+
+/// Starts the background thread which persists the counts in `data` to `name`
+/// whenever they have changed, so that commands never wait on the disk.
+pub fn start(data: Arc<Mutex<ShareMap>>, name: String) {
+    thread::spawn(move || {
+        loop {
+            thread::sleep(Duration::from_secs(SAVE_INTERVAL));
+
+            let counts = {
+                let mut data = data.lock().expect("Failed to lock client data");
+                match data.get_mut::<CommandCounter>().map(CommandCounts::take_unsaved) {
+                    Some(Ok(Some(counts))) => counts,
+                    Some(Ok(None)) | None => continue,
+                    Some(Err(err)) => {
+                        warn!("Failed to serialize command counts: {}", err);
+                        continue;
+                    },
+                }
+            };
+
+            if let Err(err) = write(&name, &counts) {
+                warn!("Failed to save command counts: {}", err);
+                // Try again at the next save.
+                let mut data = data.lock().expect("Failed to lock client data");
+                if let Some(counter) = data.get_mut::<CommandCounter>() {
+                    counter.dirty = true;
+                }
+            }
+        }
+    });
+}
+
+fn write(name: &str, counts: &str) -> Result<()> {
+    write_atomically(name, counts.as_bytes())?;
+    trace!("Saved command counts to: {}", name);
+
+    Ok(())
+}
+
+impl Default for CommandCounts {
+    fn default() -> CommandCounts {
+        CommandCounts {
+            commands: HashMap::new(),
+            users: HashMap::new(),
+            since: UTC::now(),
+            last_saved: None,
+            dirty: false,
+        }
+    }
 }
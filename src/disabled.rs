@@ -0,0 +1,74 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Stores the commands which guilds have chosen to disable.
+
+use error::Result;
+use serenity::model::GuildId;
+use std::collections::{HashMap, HashSet};
+use util::{JsonStore, data_path};
+
+const DISABLED_COMMANDS_FILE: &'static str = "disabled_commands.json";
+
+lazy_static! {
+    pub static ref DISABLED_COMMANDS: DisabledCommands =
+        DisabledCommands::new(&data_path(DISABLED_COMMANDS_FILE));
+}
+
+#[derive(Debug)]
+pub struct DisabledCommands {
+    commands: JsonStore<HashMap<String, HashSet<String>>>,
+}
+
+impl DisabledCommands {
+    fn new(name: &str) -> Self {
+        DisabledCommands { commands: JsonStore::load(name) }
+    }
+
+    /// Returns whether the command is disabled in the guild.
+    pub fn is_disabled(&self, guild_id: GuildId, command: &str) -> bool {
+        self.commands.read(|commands| {
+            commands.get(&guild_id.to_string())
+                .map_or(false, |commands| commands.contains(command))
+        })
+    }
+
+    /// Returns the commands disabled in the guild, ordered by name.
+    pub fn list(&self, guild_id: GuildId) -> Vec<String> {
+        let mut commands = self.commands.read(|commands| {
+            commands.get(&guild_id.to_string())
+                .map(|commands| commands.iter().cloned().collect::<Vec<String>>())
+                .unwrap_or_else(Vec::new)
+        });
+        commands.sort();
+        commands
+    }
+
+    /// Enables or disables the command in the guild, then persists the change.
+    pub fn set(&self, guild_id: GuildId, command: &str, disabled: bool) -> Result<()> {
+        self.commands.modify(|commands| {
+            let key = guild_id.to_string();
+            if disabled {
+                commands.entry(key).or_insert_with(HashSet::new).insert(command.to_owned());
+            } else {
+                let now_empty = match commands.get_mut(&key) {
+                    Some(guild_commands) => {
+                        guild_commands.remove(command);
+                        guild_commands.is_empty()
+                    },
+                    None => false,
+                };
+                if now_empty {
+                    commands.remove(&key);
+                }
+            }
+            Ok(())
+        })
+    }
+}
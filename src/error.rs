@@ -8,6 +8,7 @@
 // except according to those terms.
 
 use hyper;
+use hyper::status::StatusCode;
 use serde_json;
 use std::error::Error as StdError;
 use std::fmt;
@@ -18,6 +19,9 @@ use url;
 /// A convenient alias type for results for `smexybot`.
 pub type Result<T> = StdResult<T, Error>;
 
+/// A convenient alias type for results of commands.
+pub type CommandResult<T> = StdResult<T, CommandError>;
+
 /// Represents errors which occur while using Smexybot.
 #[derive(Debug)]
 pub enum Error {
@@ -27,6 +31,11 @@ pub enum Error {
     Io(io::Error),
     /// A `serde` crate error.
     Serde(serde_json::Error),
+    /// A server responded to an HTTP request with an error status.
+    Status(StatusCode),
+    /// A TOML file could not be parsed or written, as described by the
+    /// contained message.
+    Toml(String),
     /// Error while parsing a URL.
     UrlParse(url::ParseError),
 }
@@ -39,6 +48,8 @@ impl fmt::Display for Error {
             Hyper(ref e) => e.fmt(f),
             Io(ref e) => e.fmt(f),
             Serde(ref e) => e.fmt(f),
+            Status(ref status) => write!(f, "server responded with {}", status),
+            Toml(ref message) => f.write_str(message),
             UrlParse(ref e) => e.fmt(f),
         }
     }
@@ -52,6 +63,8 @@ impl StdError for Error {
             Hyper(ref e) => e.description(),
             Io(ref e) => e.description(),
             Serde(ref e) => e.description(),
+            Status(_) => "server responded with an error status",
+            Toml(ref message) => message,
             UrlParse(ref e) => e.description(),
         }
     }
@@ -63,6 +76,8 @@ impl StdError for Error {
             Hyper(ref e) => e.cause(),
             Io(ref e) => e.cause(),
             Serde(ref e) => e.cause(),
+            Status(_) |
+            Toml(_) => None,
             UrlParse(ref e) => e.cause(),
         }
     }
@@ -91,3 +106,83 @@ impl From<url::ParseError> for Error {
         Error::UrlParse(error)
     }
 }
+
+/// Represents errors which occur while running a command, and which are
+/// reported back to the user who invoked it.
+#[derive(Debug)]
+pub enum CommandError {
+    /// The user provided invalid input, described by the contained message.
+    InvalidInput(String),
+    /// Persisted data could not be read or written.
+    Io(Error),
+    /// The requested item does not exist, as described by the contained
+    /// message.
+    NotFound(String),
+    /// The user does not have permission to perform the action.
+    PermissionDenied,
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::CommandError::*;
+
+        match *self {
+            InvalidInput(ref message) |
+            NotFound(ref message) => f.write_str(message),
+            Io(_) => f.write_str("Failed to access stored data."),
+            PermissionDenied => f.write_str("You do not have permission to do that."),
+        }
+    }
+}
+
+impl StdError for CommandError {
+    fn description(&self) -> &str {
+        use self::CommandError::*;
+
+        match *self {
+            InvalidInput(ref message) |
+            NotFound(ref message) => message,
+            Io(ref e) => e.description(),
+            PermissionDenied => "permission denied",
+        }
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        use self::CommandError::*;
+
+        match *self {
+            Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for CommandError {
+    fn from(error: Error) -> CommandError {
+        CommandError::Io(error)
+    }
+}
+
+impl<'a> From<&'a str> for CommandError {
+    fn from(message: &'a str) -> CommandError {
+        CommandError::InvalidInput(message.to_owned())
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> CommandError {
+        CommandError::InvalidInput(message)
+    }
+}
+
+// The framework expects commands to fail with a `String`, which it then sends
+// to the user, so this conversion is performed at the command boundary.
+impl From<CommandError> for String {
+    fn from(error: CommandError) -> String {
+        if let CommandError::Io(ref err) = error {
+            warn!("Command failed to access stored data: {}", err);
+        }
+
+        error.to_string()
+    }
+}
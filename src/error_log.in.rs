@@ -0,0 +1,10 @@
+#[derive(Debug, Serialize)]
+struct ErrorLogEntry {
+    timestamp: DateTime<UTC>,
+    command: String,
+    user_id: u64,
+    user_name: String,
+    guild_id: Option<u64>,
+    channel_id: u64,
+    error: String,
+}
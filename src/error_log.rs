@@ -0,0 +1,72 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Records failed commands to a log file, one JSON object per line, so that
+//! they can be looked into later. Once the file grows past the configured
+//! size, it is moved aside to `<path>.1` and a new file is started.
+
+use ::current_config;
+use chrono::{DateTime, UTC};
+use error::Result;
+use serde_json;
+use serenity::model::Message;
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use util::data_path;
+
+#[cfg(feature = "nightly")]
+include!("error_log.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/error_log.rs"));
+
+/// Appends the failure of the command to the error log, if one is configured.
+pub fn record(command: &str, message: &Message, error: &str) {
+    let (path, max_size) = {
+        let config = current_config();
+        match config.error_log_path {
+            Some(ref path) => (path.clone(), config.error_log_max_size),
+            None => return,
+        }
+    };
+    let path = data_path(&path);
+
+    let entry = ErrorLogEntry {
+        timestamp: UTC::now(),
+        command: command.to_owned(),
+        user_id: message.author.id.0,
+        user_name: message.author.name.clone(),
+        guild_id: message.guild_id().map(|guild_id| guild_id.0),
+        channel_id: message.channel_id.0,
+        error: error.to_owned(),
+    };
+    if let Err(err) = append(&path, max_size, &entry) {
+        warn!("Failed to write to error log \"{}\": {}", path, err);
+    }
+}
+
+fn append(path: &str, max_size: u64, entry: &ErrorLogEntry) -> Result<()> {
+    match fs::metadata(path) {
+        Ok(ref metadata) if metadata.len() >= max_size => {
+            fs::rename(path, format!("{}.1", path))?;
+        },
+        Ok(_) => {},
+        Err(ref err) if err.kind() == ErrorKind::NotFound => {},
+        Err(err) => return Err(err.into()),
+    }
+
+    // The entry is written with a single call, so that entries from
+    // concurrent commands end up on separate lines.
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+
+    Ok(())
+}
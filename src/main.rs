@@ -35,9 +35,10 @@
 //! [serenity.rs](https://github.com/zeyla/serenity.rs) Discord API.
 
 extern crate chrono;
+extern crate chrono_tz;
+extern crate ctrlc;
 extern crate env_logger;
 extern crate hyper;
-#[cfg(any(feature = "roll", feature = "wolfram", feature = "xkcd"))]
 #[macro_use]
 extern crate lazy_static;
 #[macro_use]
@@ -50,29 +51,69 @@ extern crate serde_derive;
 extern crate serde_json;
 #[macro_use]
 extern crate serenity;
+extern crate toml;
+extern crate typemap;
 extern crate url;
+extern crate uuid;
 
+mod bot_channels;
 mod command;
 mod config;
+mod cooldown;
 mod counter;
+mod disabled;
 mod error;
+mod error_log;
+mod prefix;
+mod status;
 mod util;
+mod welcome;
 
+use bot_channels::BOT_CHANNELS;
 use chrono::{DateTime, UTC};
 use config::Config;
-use counter::CommandCounter;
+use cooldown::{CommandCooldowns, Cooldowns};
+use counter::{CommandCounter, CommandCounts};
+use disabled::DISABLED_COMMANDS;
+use prefix::PREFIXES;
 use serenity::Client;
-use serenity::client::LoginType;
-use serenity::ext::framework::Framework;
-use serenity::model::UserId;
-use std::collections::HashMap;
-use std::env;
-use util::{check_msg, timestamp_to_string};
+use serenity::client::{CACHE, LoginType};
+use serenity::ext::framework::{CreateCommand, Framework};
+use serenity::model::{ChannelId, GuildId, UserId};
+use std::{cmp, env, process, thread};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard};
+use std::time::Duration;
+use typemap::ShareMap;
+use util::{check_msg, check_msg_retry, data_path, is_guild_channel, is_owner,
+           timestamp_to_string_tz};
+use welcome::WELCOMES;
 
-const RATE_LIMIT_MESSAGE: &'static str = "Try this again in %time% seconds.";
+const CONFIG_FILE: &'static str = "config.json";
+/// The flag which makes the bot check its config and exit, rather than start.
+const CHECK_CONFIG_FLAG: &'static str = "--check-config";
+/// The config file used if `CONFIG_FILE` does not exist.
+const TOML_CONFIG_FILE: &'static str = "config.toml";
+const CONFIG_PATH_ENV_VAR: &'static str = "SMEXYBOT_CONFIG";
+const COUNTER_FILE: &'static str = "counter.json";
+const SHARDS_ENV_VAR: &'static str = "SMEXYBOT_SHARDS";
+/// The number of times to try replying with a command's error.
+const ERROR_REPLY_ATTEMPTS: u32 = 3;
 
 lazy_static! {
-    static ref CONFIG: Config = Config::new(Some("config.json"));
+    static ref CONFIG_PATH: String = env::var(CONFIG_PATH_ENV_VAR)
+        .unwrap_or_else(|_| default_config_path().to_owned());
+    static ref CONFIG: RwLock<Config> = RwLock::new(Config::new(Some(&CONFIG_PATH)));
+    static ref COUNTER_PATH: String = data_path(COUNTER_FILE);
+    // The aliases which were registered, mapped to the names of the commands
+    // they stand for.
+    static ref ALIASES: RwLock<HashMap<String, String>> = RwLock::new(HashMap::new());
+    // The names of the commands which were registered, excluding aliases.
+    static ref COMMANDS: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+    // The guilds the bot is in, used to tell newly joined guilds apart from
+    // those created on startup.
+    static ref KNOWN_GUILDS: Mutex<HashSet<GuildId>> = Mutex::new(HashSet::new());
     static ref UPTIME: DateTime<UTC> = UTC::now();
 }
 
@@ -80,18 +121,47 @@ fn main() {
     // Initialize the `env_logger` to provide logging output.
     env_logger::init().expect("Failed to initialize env_logger");
 
+    if env::args().skip(1).any(|arg| arg == CHECK_CONFIG_FLAG) {
+        process::exit(check_config());
+    }
+
+    // Load the config before anything else needs it, so that any problems with
+    // it are reported up front.
+    info!("Using config path: \"{}\"", *CONFIG_PATH);
+    debug!("Loaded config for: {}", current_config().bot_name);
+
     // Initialize the `UPTIME` variable.
-    debug!("Initialized at: {}", timestamp_to_string(&*UPTIME));
+    debug!("Initialized at: {}", timestamp_to_string_tz(&*UPTIME));
 
     // Create a client for a user.
     let (_, mut client) = login();
 
     {
         let mut data = client.data.lock().expect("Failed to lock client data");
-        data.insert::<CommandCounter>(HashMap::default());
+        data.insert::<CommandCooldowns>(Cooldowns::default());
+        data.insert::<CommandCounter>(CommandCounts::load(&COUNTER_PATH));
+    }
+
+    install_shutdown_handler(client.data.clone());
+    counter::start(client.data.clone(), COUNTER_PATH.clone());
+
+    // Reminders are sent via the REST API, so this only needs to wait until
+    // the client has logged in.
+    #[cfg(feature = "remind")]
+    {
+        command::remind::start();
+    }
+    #[cfg(feature = "tag")]
+    {
+        command::tag::start();
     }
 
-    client.on_ready(|_context, ready| {
+    client.on_ready(|context, ready| {
+        status::start(context, ready.shard.map_or(0, |s| s[0]));
+        KNOWN_GUILDS.lock()
+            .expect("Failed to lock known guilds")
+            .extend(ready.guilds.iter().map(|guild| guild.id()));
+
         let shard_info = if let Some(s) = ready.shard {
             Some(format!("shard {}/{} ", s[0] + 1, s[1]))
         } else {
@@ -106,87 +176,494 @@ fn main() {
         );
     });
 
+    // Guilds the bot was already in are also created on startup, so only
+    // those which weren't in the ready event are new.
+    client.on_guild_create(|_context, guild| {
+        let joined = KNOWN_GUILDS.lock()
+            .expect("Failed to lock known guilds")
+            .insert(guild.id);
+        if joined {
+            log_guild_event(&format!("Joined guild \"{}\" ({}) with {} members",
+                                     guild.name,
+                                     guild.id,
+                                     guild.member_count));
+        }
+    });
+
+    client.on_guild_delete(|_context, partial_guild, guild| {
+        KNOWN_GUILDS.lock().expect("Failed to lock known guilds").remove(&partial_guild.id);
+        let members = guild.map_or_else(|| "an unknown number of".to_owned(),
+                                        |guild| guild.member_count.to_string());
+        log_guild_event(&format!("Left guild \"{}\" ({}) with {} members",
+                                 partial_guild.name,
+                                 partial_guild.id,
+                                 members));
+    });
+
+    client.on_guild_member_add(|_context, guild_id, member| {
+        let welcome = match WELCOMES.get(guild_id) {
+            Some(welcome) => welcome,
+            None => return,
+        };
+        let guild_name = CACHE.read()
+            .ok()
+            .and_then(|cache| cache.guilds.get(&guild_id).map(|guild| guild.name.clone()))
+            .unwrap_or_else(|| "the server".to_owned());
+
+        // The channel may have been deleted, or moved to another guild, since
+        // the welcome message was set.
+        let channel_id = ChannelId(welcome.channel_id);
+        if !is_guild_channel(guild_id, channel_id) {
+            warn!("Welcome channel {} is not in guild {}", channel_id.0, guild_id.0);
+            return;
+        }
+
+        let content = welcome.render(&format!("<@{}>", member.user.id), &guild_name);
+        check_msg(channel_id.say(&content));
+    });
+
+    client.on_reaction_add(|_context, reaction| command::pagination::handle_reaction(&reaction));
+
     client.with_framework(build_framework);
 
-    if let Err(err) = client.start_autosharded() {
-        error!("Client error: {:?}", err);
+    start(&mut client);
+}
+
+// Starts the client, retrying with an exponentially increasing delay if it
+// fails, and exits once the configured number of attempts is exhausted.
+fn start(client: &mut Client) {
+    // The config must not stay locked while the client runs, or it could
+    // never be reloaded.
+    let (max_attempts, retry_delay, shard_count) = {
+        let config = current_config();
+        (config.max_start_attempts, config.start_retry_delay, shard_count(&config))
+    };
+
+    match shard_count {
+        Some(shard_count) => info!("Using {} shards", shard_count),
+        None => info!("Using the recommended number of shards"),
+    }
+
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        info!("Starting client (attempt {}/{})", attempt, max_attempts);
+        let result = match shard_count {
+            Some(shard_count) => client.start_shards(shard_count),
+            None => client.start_autosharded(),
+        };
+        match result {
+            Ok(()) => return,
+            Err(err) => error!("Client error: {:?}", err),
+        }
+
+        if attempt >= max_attempts {
+            error!("Failed to start client after {} attempts, giving up", attempt);
+            process::exit(1);
+        }
+
+        let delay = retry_delay.saturating_mul(1 << cmp::min(attempt - 1, 10));
+        warn!("Retrying in {} seconds", delay);
+        thread::sleep(Duration::from_secs(delay));
     }
 }
 
 // Configures the `Framework` used by serenity, and registers the handlers for
 // any enabled commands.
 fn build_framework(framework: Framework) -> Framework {
+    let config = current_config();
     let mut framework = framework.configure(|c| {
-            c.rate_limit_message(RATE_LIMIT_MESSAGE)
-                .prefix(&CONFIG.command_prefix)
-                .owners(CONFIG.owners.iter().map(|id| UserId(*id)).collect())
+            c.rate_limit_message(&config.rate_limit_message)
+                .prefix(&config.command_prefix)
+                .on_mention(config.respond_to_mention)
+                .dynamic_prefix(|_context, message| {
+                    message.guild_id().and_then(|guild_id| PREFIXES.get(guild_id))
+                })
+                .owners(config.owners.iter().map(|id| UserId(*id)).collect())
         })
         .before(|context, message, command_name| {
+            // Aliases are counted, logged and configured under the name of the
+            // command they stand for.
+            let command_name = &canonical_name(command_name);
             info!(
                 "Got command '{}' from user '{}'",
                 command_name,
                 message.author.name,
             );
 
-            // Increment the number of times this command has been run. If the
-            // command's name does not exist in the counter, add a default value of
-            // 0.
+            // Direct messages are reserved for the bot's owners, who may
+            // always use every command, anywhere.
+            let guild_id = message.guild_id();
+            if guild_id.is_none() && !is_owner(message.author.id) {
+                return false;
+            }
+
+            if let Some(guild_id) = guild_id {
+                if !is_owner(message.author.id) &&
+                   DISABLED_COMMANDS.is_disabled(guild_id, command_name) {
+                    if current_config().disabled_command_reply {
+                        check_msg(context.say("That command is disabled in this server."));
+                    }
+                    return false;
+                }
+
+                // `botchannel` is always allowed, so that a server can't lock
+                // itself out of changing its bot channels.
+                if !is_owner(message.author.id) && command_name != "botchannel" &&
+                   !BOT_CHANNELS.is_allowed(guild_id, message.channel_id) {
+                    return false;
+                }
+            }
+
             let mut data = context.data.lock().expect("Failed to lock context data");
+
+            let cooldown = current_config().cooldowns.get(command_name).cloned();
+            if let Some(cooldown) = cooldown {
+                let cooldowns = data.get_mut::<CommandCooldowns>().unwrap();
+                let remaining = cooldowns.check(&command_name, message.author.id, cooldown);
+                if let Some(remaining) = remaining {
+                    // Round up, so that a user is never told to wait 0 seconds.
+                    let seconds = (remaining.num_milliseconds() + 999) / 1000;
+                    let reply = current_config()
+                        .rate_limit_message
+                        .replace("%time%", &seconds.to_string());
+                    check_msg(context.say(&reply));
+                    return false;
+                }
+            }
+
+            // Increment the number of times this command has been run, both in
+            // total and by this user.
             let counter = data.get_mut::<CommandCounter>().unwrap();
-            let entry = counter.entry(command_name.clone()).or_insert(0);
-            *entry += 1;
+            counter.increment(&command_name, message.author.id);
 
             true
         })
-        .after(|context, _message, command_name, error| {
+        .after(|context, message, command_name, error| {
+            let command_name = &canonical_name(command_name);
             if let Err(err) = error {
-                check_msg(context.say(&err));
+                error_log::record(&command_name, message, &err);
+                check_msg_retry(|| context.say(&err), ERROR_REPLY_ATTEMPTS);
             } else {
                 debug!("Processed command '{}'", command_name);
             }
         });
 
+    // Registers the command, as well as any aliases of it. A command always
+    // takes precedence over an alias of the same name.
+    let mut commands = HashSet::new();
+    let mut aliases = HashMap::new();
+    macro_rules! register {
+        ($name:expr, $f:expr) => {{
+            let name: &str = $name;
+            let f = command_builder($f);
+            // The description is shared with the `help` command.
+            let desc = command::registry::find(name).map(|command| command.description);
+            let f = command_builder(move |c: CreateCommand| {
+                f(match desc {
+                    Some(desc) => c.desc(desc),
+                    None => c,
+                })
+            });
+            if aliases.remove(name).is_some() {
+                warn!("Ignoring alias '{}', as a command has the same name", name);
+            }
+            framework = framework.command(name, &f);
+            commands.insert(name.to_owned());
+
+            for (alias, target) in &config.aliases {
+                if target != name {
+                    continue;
+                }
+                if commands.contains(alias) {
+                    warn!("Ignoring alias '{}', as a command has the same name", alias);
+                    continue;
+                }
+                framework = framework.command(alias.as_str(), &f);
+                aliases.insert(alias.clone(), target.clone());
+            }
+        }};
+    }
+
+    #[cfg(feature = "about")]
+    {
+        register!("about", |c| c.exec(command::about::about));
+    }
+    #[cfg(feature = "avatar")]
+    {
+        register!("avatar", |c| c.exec(command::avatar::avatar));
+    }
+    #[cfg(feature = "ban")]
+    {
+        register!("ban", |c| c.exec(command::ban::ban));
+    }
+    #[cfg(feature = "botchannel")]
+    {
+        register!("botchannel", |c| c.exec(command::botchannel::botchannel));
+    }
+    #[cfg(feature = "choose")]
+    {
+        register!("choose", |c| c.exec(command::choose::choose));
+    }
+    #[cfg(feature = "convert")]
+    {
+        register!("convert", |c| c.exec(command::convert::convert));
+    }
+    #[cfg(feature = "debug")]
+    {
+        register!("debug", |c| c.exec(command::debug::debug));
+    }
+    #[cfg(feature = "eightball")]
+    {
+        register!("8ball", |c| c.exec(command::eightball::eightball));
+    }
+    #[cfg(feature = "flip")]
+    {
+        register!("flip", |c| c.exec(command::flip::flip));
+    }
     #[cfg(feature = "fuyu")]
     {
-        framework = framework.command("fuyu", |c| c.exec(command::fuyu::fuyu));
+        register!("fuyu", |c| c.exec(command::fuyu::fuyu));
     }
     #[cfg(feature = "help")]
     {
-        use serenity::ext::framework::help_commands;
-        framework = framework.command("help", |c| c.exec_help(help_commands::plain));
+        register!("help", |c| c.exec(command::help::help));
+    }
+    #[cfg(feature = "kick")]
+    {
+        register!("kick", |c| c.exec(command::kick::kick));
     }
     #[cfg(feature = "ping")]
     {
-        framework = framework.command("ping", |c| {
-            c.desc("Responds with 'Pong', as well as a latency estimate.")
-                .exec(command::ping::ping)
-                .owners_only(true)
+        register!("ping", |c| {
+            c.exec(command::ping::ping)
+                .owners_only(config.ping_owners_only)
         });
     }
+    #[cfg(feature = "poll")]
+    {
+        register!("poll", |c| c.exec(command::poll::poll));
+    }
+    #[cfg(feature = "prefix")]
+    {
+        register!("prefix", |c| c.exec(command::prefix::prefix));
+    }
+    #[cfg(feature = "purge")]
+    {
+        register!("purge", |c| c.exec(command::purge::purge));
+    }
+    #[cfg(feature = "reload")]
+    {
+        register!("reload", |c| c.exec(command::reload::reload));
+    }
+    #[cfg(feature = "remind")]
+    {
+        register!("remindme", |c| c.exec(command::remind::remindme));
+    }
+    #[cfg(feature = "role")]
+    {
+        register!("role", |c| c.exec(command::role::role));
+    }
     #[cfg(feature = "roll")]
     {
-        framework = framework.command("roll", |c| c.exec(command::roll::roll));
+        register!("roll", |c| c.exec(command::roll::roll));
+    }
+    #[cfg(feature = "say")]
+    {
+        register!("say", |c| c.exec(command::say::say));
+    }
+    #[cfg(feature = "serverinfo")]
+    {
+        register!("serverinfo", |c| c.exec(command::serverinfo::serverinfo));
+    }
+    #[cfg(feature = "shutdown")]
+    {
+        register!("shutdown", |c| c.exec(command::shutdown::shutdown));
     }
     #[cfg(feature = "stats")]
     {
-        framework = framework.command("stats", |c| c.exec(command::stats::stats));
+        register!("stats", |c| c.exec(command::stats::stats));
     }
     #[cfg(feature = "tag")]
     {
-        framework = framework.command("tag", |c| c.exec(command::tag::tag));
+        register!("tag", |c| c.exec(command::tag::tag));
+    }
+    #[cfg(feature = "toggle")]
+    {
+        register!("command", |c| c.exec(command::toggle::command));
+    }
+    #[cfg(feature = "uptime")]
+    {
+        register!("uptime", |c| c.exec(command::uptime::uptime));
+    }
+    #[cfg(feature = "urban")]
+    {
+        register!("urban", |c| c.exec(command::urban::urban));
+    }
+    #[cfg(feature = "userinfo")]
+    {
+        register!("userinfo", |c| c.exec(command::userinfo::userinfo));
+    }
+    #[cfg(feature = "weather")]
+    {
+        register!("weather", |c| c.exec(command::weather::weather));
+    }
+    #[cfg(feature = "welcome")]
+    {
+        register!("welcome", |c| c.exec(command::welcome::welcome));
     }
     #[cfg(feature = "wolfram")]
     {
-        framework = framework.command("wolfram", |c| c.exec(command::wolfram_alpha::wolfram));
+        register!("wolfram", |c| c.exec(command::wolfram_alpha::wolfram));
     }
     #[cfg(feature = "xkcd")]
     {
-        framework = framework.command("xkcd", |c| c.exec(command::xkcd::xkcd));
+        register!("xkcd", |c| c.exec(command::xkcd::xkcd));
     }
 
+    *ALIASES.write().expect("Failed to lock aliases") = aliases;
+    *COMMANDS.write().expect("Failed to lock commands") = commands;
+
     framework
 }
 
+// Allows the type of a command's builder closure to be inferred when it is not
+// passed directly to `Framework::command`.
+fn command_builder<F>(f: F) -> F
+    where F: Fn(CreateCommand) -> CreateCommand,
+{
+    f
+}
+
+// Returns the name of the command the alias stands for, or the name itself if
+// it is not an alias.
+fn canonical_name(name: &str) -> String {
+    ALIASES.read()
+        .expect("Failed to lock aliases")
+        .get(name)
+        .cloned()
+        .unwrap_or_else(|| name.to_owned())
+}
+
+// Returns whether a command, or an alias of one, was registered by the name.
+fn is_command(name: &str) -> bool {
+    COMMANDS.read().expect("Failed to lock commands").contains(&canonical_name(name))
+}
+
+// Flushes any persisted state to disk and exits when the process is
+// interrupted or terminated, so that it isn't left half-written.
+fn install_shutdown_handler(data: Arc<Mutex<ShareMap>>) {
+    let result = ctrlc::set_handler(move || shutdown(&data));
+
+    // Not every platform supports this, but the bot can still run without it.
+    if let Err(err) = result {
+        warn!("Failed to install shutdown handler: {}", err);
+    }
+}
+
+/// Flushes any persisted state to disk, then exits the process.
+fn shutdown(data: &Mutex<ShareMap>) {
+    info!("Shutting down");
+
+    {
+        let mut data = data.lock().expect("Failed to lock client data");
+        if let Some(counter) = data.get_mut::<CommandCounter>() {
+            if let Err(err) = counter.save(&COUNTER_PATH) {
+                warn!("Failed to save command counts: {}", err);
+            }
+        }
+    }
+
+    #[cfg(feature = "tag")]
+    {
+        if let Err(err) = command::tag::save() {
+            warn!("Failed to save tags: {}", err);
+        }
+    }
+
+    process::exit(0);
+}
+
+// Logs the joining or leaving of a guild, and announces it in the guild log
+// channel, if one is configured.
+fn log_guild_event(event: &str) {
+    info!("{}", event);
+
+    let channel = current_config().guild_log_channel;
+    if let Some(channel) = channel {
+        check_msg(ChannelId(channel).say(event));
+    }
+}
+
+// Returns the number of shards to start, if a fixed number has been chosen
+// rather than the number recommended by Discord.
+fn shard_count(config: &Config) -> Option<u64> {
+    match env::var(SHARDS_ENV_VAR).map(|shards| shards.parse::<u64>()) {
+        Ok(Ok(0)) | Ok(Err(_)) => {
+            warn!("Ignoring invalid {} value, expected a positive number", SHARDS_ENV_VAR);
+            config.shard_count
+        },
+        Ok(Ok(shard_count)) => Some(shard_count),
+        Err(_) => config.shard_count,
+    }
+}
+
+/// Returns a read handle to the currently loaded `Config`.
+fn current_config() -> RwLockReadGuard<'static, Config> {
+    CONFIG.read().expect("Failed to lock config")
+}
+
+// Prints any problems with the config, and with the tags if they are enabled,
+// without loading them for use. Returns the code to exit with, which is
+// non-zero if anything is invalid.
+fn check_config() -> i32 {
+    println!("Checking config: \"{}\"", *CONFIG_PATH);
+    if !Path::new(&*CONFIG_PATH).exists() {
+        println!("error: The config file does not exist");
+        return 1;
+    }
+    let config = match Config::read(&CONFIG_PATH) {
+        Ok((config, _)) => config,
+        Err(err) => {
+            println!("error: Failed to read config: {}", err);
+            return 1;
+        },
+    };
+
+    #[allow(unused_mut)]
+    let mut errors = config.errors();
+    #[cfg(feature = "tag")]
+    {
+        match command::tag::check(&config.data_dir) {
+            Ok(count) => println!("Read {} tags", count),
+            Err(err) => errors.push(format!("Failed to read tags: {}", err)),
+        }
+    }
+
+    for warning in config.warnings() {
+        println!("warning: {}", warning);
+    }
+    for error in &errors {
+        println!("error: {}", error);
+    }
+    if errors.is_empty() {
+        println!("The config is valid");
+        0
+    } else {
+        1
+    }
+}
+
+// Returns the config file to use when none is specified. JSON is preferred, so
+// the TOML file is only used if there is no JSON file.
+fn default_config_path() -> &'static str {
+    if !Path::new(CONFIG_FILE).exists() && Path::new(TOML_CONFIG_FILE).exists() {
+        TOML_CONFIG_FILE
+    } else {
+        CONFIG_FILE
+    }
+}
+
 // Creates a `Client`.
 fn login() -> (LoginType, Client) {
     debug!("Attempting to login");
@@ -0,0 +1,50 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Stores the command prefixes which guilds have chosen to use in place of the
+//! default `command_prefix` from the `Config`.
+
+use error::Result;
+use serenity::model::GuildId;
+use std::collections::HashMap;
+use util::{JsonStore, data_path};
+
+const PREFIXES_FILE: &'static str = "prefixes.json";
+
+lazy_static! {
+    pub static ref PREFIXES: Prefixes = Prefixes::new(&data_path(PREFIXES_FILE));
+}
+
+#[derive(Debug)]
+pub struct Prefixes {
+    prefixes: JsonStore<HashMap<String, String>>,
+}
+
+impl Prefixes {
+    fn new(name: &str) -> Self {
+        Prefixes { prefixes: JsonStore::load(name) }
+    }
+
+    /// Returns the prefix configured for the guild, if any.
+    pub fn get(&self, guild_id: GuildId) -> Option<String> {
+        self.prefixes.read(|prefixes| prefixes.get(&guild_id.to_string()).cloned())
+    }
+
+    /// Sets the prefix for the guild, or removes the guild's prefix if `None`
+    /// is given, then persists the change.
+    pub fn set(&self, guild_id: GuildId, prefix: Option<String>) -> Result<()> {
+        self.prefixes.modify(|prefixes| {
+            match prefix {
+                Some(prefix) => prefixes.insert(guild_id.to_string(), prefix),
+                None => prefixes.remove(&guild_id.to_string()),
+            };
+            Ok(())
+        })
+    }
+}
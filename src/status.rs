@@ -0,0 +1,64 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Rotates the bot's status through the messages configured in `statuses`.
+
+use ::current_config;
+use serenity::client::{CACHE, Context};
+use std::cmp;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+lazy_static! {
+    // The shards whose status is already being rotated, as `on_ready` fires
+    // again whenever a shard reconnects.
+    static ref ROTATING_SHARDS: Mutex<HashSet<u64>> = Mutex::new(HashSet::new());
+}
+
+/// Starts the background thread which rotates the status of the context's
+/// shard, unless one is already running.
+pub fn start(context: Context, shard: u64) {
+    if !ROTATING_SHARDS.lock().expect("Failed to lock rotating shards").insert(shard) {
+        return;
+    }
+
+    thread::spawn(move || {
+        let mut index = 0;
+        loop {
+            // Read the config each time, so that a reload takes effect.
+            let (status, interval) = {
+                let config = current_config();
+                let status = if config.statuses.is_empty() {
+                    None
+                } else {
+                    Some(config.statuses[index % config.statuses.len()].clone())
+                };
+                (status, config.status_interval)
+            };
+
+            if let Some(status) = status {
+                context.set_game_name(&substitute(&status));
+                index = index.wrapping_add(1);
+            }
+
+            thread::sleep(Duration::from_secs(cmp::max(interval, 1)));
+        }
+    });
+}
+
+// Replaces the placeholders in the status with their current values.
+fn substitute(status: &str) -> String {
+    let guilds = CACHE.read()
+        .map(|cache| cache.guilds.len().to_string())
+        .unwrap_or_else(|_| "?".to_owned());
+
+    status.replace("%guilds%", &guilds)
+}
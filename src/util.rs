@@ -7,15 +7,56 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use chrono::{DateTime, Duration, UTC};
+use ::current_config;
+use error::{Error as SmexybotError, Result as SmexybotResult};
+use chrono::{DateTime, Duration, TimeZone, UTC};
+use chrono_tz::Tz;
 use rand::{self, Rng};
-use serenity::Result as SerenityResult;
-use serenity::model::Message;
+use hyper::Url;
+use hyper::client::{Client, Response};
+use hyper::status::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json;
+use serenity::{Error as SerenityError, Result as SerenityResult};
+use serenity::client::ClientError;
+use serenity::client::{CACHE, Context};
+use serenity::model::{ChannelId, GuildId, Message, UserId};
+use serenity::model::permissions::Permissions;
 use serenity::utils::Colour;
+use std::cmp;
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
 use std::error::Error;
-use std::fmt::Debug;
-use std::hash::Hash;
+use std::fmt::{Debug, Display};
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::result::Result as StdResult;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration as StdDuration;
+use uuid::Uuid;
+
+/// The number of seconds to wait on an HTTP connection before giving up.
+const HTTP_TIMEOUT: u64 = 10;
+
+lazy_static! {
+    static ref HTTP_CLIENT: Client = {
+        let mut client = Client::new();
+        client.set_read_timeout(Some(StdDuration::from_secs(HTTP_TIMEOUT)));
+        client.set_write_timeout(Some(StdDuration::from_secs(HTTP_TIMEOUT)));
+        client
+    };
+}
+
+/// Returns the HTTP client shared by all commands which query external APIs,
+/// so that connections are reused and every request is subject to the same
+/// timeouts.
+#[inline]
+pub fn http_client() -> &'static Client {
+    &HTTP_CLIENT
+}
 
 /// Takes a `Vec<T>` and splits it into a head and a tail.
 #[inline]
@@ -27,6 +68,83 @@ pub fn split_list<T>(list: Vec<T>) -> (Option<T>, Vec<T>) {
     (head, tail)
 }
 
+/// Splits the text into arguments on whitespace, except for whitespace within
+/// double quotes, e.g. `"a b" c` becomes `["a b", "c"]`.
+pub fn split_quoted(text: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in text.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    args.push(current.clone());
+                    current.clear();
+                }
+            },
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        args.push(current);
+    }
+
+    args
+}
+
+/// Calls `f` until it succeeds, up to `attempts` times in total, waiting
+/// `delay` milliseconds after the first failure and twice as long after each
+/// one after that. Errors for which `is_transient` returns `false` are returned
+/// without retrying.
+///
+/// Callers usually pass the `http_attempts` and `http_retry_delay` from the
+/// config.
+pub fn retry<T, E, F, P>(mut f: F, is_transient: P, attempts: u32, delay: u64) -> StdResult<T, E>
+    where F: FnMut() -> StdResult<T, E>,
+          P: Fn(&E) -> bool,
+          E: Display,
+{
+    let attempts = cmp::max(attempts, 1);
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(ref why) if attempt < attempts && is_transient(why) => {
+                warn!("Request failed (attempt {}/{}), retrying: {}", attempt, attempts, why);
+                thread::sleep(StdDuration::from_millis(delay << (attempt - 1)));
+                attempt += 1;
+            },
+            result => return result,
+        }
+    }
+}
+
+/// Sends a GET request to the URL, retrying as per `retry` if it could not be
+/// sent or the server responded with a server error. Other error statuses,
+/// e.g. 404 Not Found, are left to the caller to handle.
+pub fn get_with_retry(
+    client: &Client,
+    url: &Url,
+    attempts: u32,
+    delay: u64
+) -> SmexybotResult<Response> {
+    let send = || {
+        let response = try!(client.get(url.clone()).send());
+        if response.status.is_server_error() {
+            return Err(SmexybotError::Status(response.status));
+        }
+
+        Ok(response)
+    };
+    let is_transient = |why: &SmexybotError| match *why {
+        SmexybotError::Hyper(_) |
+        SmexybotError::Status(_) => true,
+        _ => false,
+    };
+
+    retry(send, is_transient, attempts, delay)
+}
+
 /// Checks that a message successfully sent; if not, then logs why.
 #[inline]
 pub fn check_msg(result: SerenityResult<Message>) {
@@ -35,11 +153,208 @@ pub fn check_msg(result: SerenityResult<Message>) {
     }
 }
 
+/// The outcome of sending a message with `check_msg_retry`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Delivery {
+    /// The message was sent.
+    Sent,
+    /// The message could not be sent due to what is likely a temporary
+    /// problem, so sending it again later may succeed.
+    Failed,
+    /// The message could not be sent, and sending it again will not help,
+    /// e.g. as the channel no longer exists.
+    Rejected,
+}
+
+/// Sends a message by calling `send`, retrying up to `attempts` times in total
+/// if sending fails due to what is likely a temporary problem, such as a
+/// server error. Logs why if the message could not be sent.
+pub fn check_msg_retry<F>(mut send: F, attempts: u32) -> Delivery
+    where F: FnMut() -> SerenityResult<Message>,
+{
+    const RETRY_DELAY_MILLISECONDS: u64 = 500;
+
+    for attempt in 1..(attempts + 1) {
+        let why = match send() {
+            Ok(_) => return Delivery::Sent,
+            Err(why) => why,
+        };
+
+        let status = match why {
+            SerenityError::Client(ClientError::InvalidRequest(status)) => Some(status),
+            _ => None,
+        };
+        let transient = match status {
+            Some(status) => status.is_server_error() || status == StatusCode::TooManyRequests,
+            None => match why {
+                SerenityError::Hyper(_) => true,
+                _ => false,
+            },
+        };
+
+        match status {
+            Some(status) => {
+                error!("Error sending message (attempt {}/{}, status {}): {:?}",
+                       attempt,
+                       attempts,
+                       status,
+                       why)
+            },
+            None => error!("Error sending message (attempt {}/{}): {:?}", attempt, attempts, why),
+        }
+        if !transient {
+            return Delivery::Rejected;
+        }
+        if attempt < attempts {
+            thread::sleep(StdDuration::from_millis(RETRY_DELAY_MILLISECONDS * u64::from(attempt)));
+        }
+    }
+
+    Delivery::Failed
+}
+
+/// Returns whether the specified user is one of the bot's owners.
+#[inline]
+pub fn is_owner(user_id: UserId) -> bool {
+    current_config().owners.contains(&user_id.0)
+}
+
+/// Returns whether the specified user may manage the specified guild. The
+/// bot's owners are always considered to be admins.
+pub fn is_guild_admin(guild_id: GuildId, user_id: UserId) -> bool {
+    if is_owner(user_id) {
+        return true;
+    }
+
+    let cache = match CACHE.read() {
+        Ok(cache) => cache,
+        Err(_) => return false,
+    };
+    cache.guilds
+        .get(&guild_id)
+        .map(|guild| guild.member_permissions(user_id).manage_guild())
+        .unwrap_or(false)
+}
+
+/// Returns whether the specified user has all of the specified permissions in
+/// the specified guild. Unlike `is_guild_admin`, the bot's owners get no
+/// special treatment, as this is used to moderate guilds they may not run.
+pub fn has_permissions(guild_id: GuildId, user_id: UserId, permissions: Permissions) -> bool {
+    let cache = match CACHE.read() {
+        Ok(cache) => cache,
+        Err(_) => return false,
+    };
+    cache.guilds
+        .get(&guild_id)
+        .map(|guild| guild.member_permissions(user_id).contains(permissions))
+        .unwrap_or(false)
+}
+
+/// Returns whether the specified channel belongs to the specified guild.
+pub fn is_guild_channel(guild_id: GuildId, channel_id: ChannelId) -> bool {
+    let cache = match CACHE.read() {
+        Ok(cache) => cache,
+        Err(_) => return false,
+    };
+    cache.guilds
+        .get(&guild_id)
+        .map(|guild| guild.channels.contains_key(&channel_id))
+        .unwrap_or(false)
+}
+
+/// Returns whether the bot itself has all of the specified permissions in the
+/// specified guild.
+pub fn bot_has_permissions(guild_id: GuildId, permissions: Permissions) -> bool {
+    bot_id().map_or(false, |bot_id| has_permissions(guild_id, bot_id, permissions))
+}
+
+/// Returns the ID of the bot's own user.
+pub fn bot_id() -> Option<UserId> {
+    CACHE.read().ok().map(|cache| cache.user.id)
+}
+
+/// Returns whether the first user ranks above the second in the specified
+/// guild's role hierarchy, and so may moderate them. The guild's owner ranks
+/// above everyone else.
+pub fn outranks(guild_id: GuildId, user_id: UserId, target_id: UserId) -> bool {
+    let cache = match CACHE.read() {
+        Ok(cache) => cache,
+        Err(_) => return false,
+    };
+    let guild = match cache.guilds.get(&guild_id) {
+        Some(guild) => guild,
+        None => return false,
+    };
+
+    if target_id == guild.owner_id {
+        return false;
+    }
+    if user_id == guild.owner_id {
+        return true;
+    }
+
+    let position = |user_id: UserId| {
+        guild.members
+            .get(&user_id)
+            .and_then(|member| {
+                member.roles
+                    .iter()
+                    .filter_map(|role_id| guild.roles.get(role_id))
+                    .map(|role| role.position)
+                    .max()
+            })
+            .unwrap_or(0)
+    };
+    position(user_id) > position(target_id)
+}
+
 /// Generates a random RGB colour.
 #[inline]
 pub fn random_colour() -> Colour {
     let mut rng = rand::thread_rng();
-    Colour::new(rng.gen_range::<u32>(0, 0xFFFFFF + 1))
+    readable_colour(rng.gen::<f64>(), rng.gen::<f64>(), rng.gen::<f64>())
+}
+
+/// Generates an RGB colour from the seed, such that the same seed always
+/// results in the same colour, e.g. to give each tag its own colour.
+pub fn colour_from_hash(seed: &str) -> Colour {
+    let mut hasher = DefaultHasher::new();
+    seed.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    // Each component is picked by a different 16 bits of the hash.
+    #[cfg_attr(feature = "clippy", allow(cast_precision_loss))]
+    let fraction = |shift: u64| ((hash >> shift) & 0xFFFF) as f64 / 65536.0;
+    readable_colour(fraction(0), fraction(16), fraction(32))
+}
+
+// Maps the fractions, each between 0 and 1, to a colour. Colours which are too
+// dark, too light or too grey are hard to see against Discord's themes, so
+// saturation and value are kept in a band which stands out against both.
+fn readable_colour(hue: f64, saturation: f64, value: f64) -> Colour {
+    let (red, green, blue) = hsv_to_rgb(hue * 360.0, 0.5 + saturation * 0.4, 0.6 + value * 0.35);
+    Colour::new((u32::from(red) << 16) | (u32::from(green) << 8) | u32::from(blue))
+}
+
+// Converts a colour from HSV, with the hue in degrees and the saturation and
+// value between 0 and 1, to RGB.
+fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> (u8, u8, u8) {
+    let chroma = value * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (red, green, blue) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let offset = value - chroma;
+
+    #[cfg_attr(feature = "clippy", allow(cast_possible_truncation, cast_sign_loss))]
+    let to_byte = |component: f64| ((component + offset) * 255.0).round() as u8;
+    (to_byte(red), to_byte(green), to_byte(blue))
 }
 
 /// Takes two `HashMap`s, merges them together, and returns the result.
@@ -57,6 +372,51 @@ pub fn merge<K: Hash + Eq, V>(first: HashMap<K, V>, second: HashMap<K, V>) -> Ha
     merged
 }
 
+/// Joins `items` with `separator` into pages, each of which is at most
+/// `max_len` characters long. An item which is longer than `max_len` by itself
+/// is placed on its own page.
+pub fn paginate(items: &[String], separator: &str, max_len: usize) -> Vec<String> {
+    let mut pages = Vec::new();
+    let mut page = String::new();
+    for item in items {
+        if !page.is_empty() &&
+           page.chars().count() + separator.chars().count() + item.chars().count() > max_len {
+            pages.push(page);
+            page = String::new();
+        }
+        if !page.is_empty() {
+            page.push_str(separator);
+        }
+        page.push_str(item);
+    }
+    if !page.is_empty() {
+        pages.push(page);
+    }
+
+    pages
+}
+
+/// Truncates `text` to at most `max_len` characters, cutting at the last word
+/// boundary and appending an ellipsis if any truncation occurs.
+pub fn truncate_words(text: &str, max_len: usize) -> String {
+    const ELLIPSIS: &'static str = "...";
+
+    if text.chars().count() <= max_len {
+        return text.to_owned();
+    }
+
+    // Work in characters rather than bytes, so that a multibyte character is
+    // never split.
+    let keep = max_len.saturating_sub(ELLIPSIS.len());
+    let truncated = text.chars().take(keep).collect::<String>();
+    let truncated = match truncated.rfind(char::is_whitespace) {
+        Some(index) => truncated[..index].trim_right(),
+        None => &truncated,
+    };
+
+    format!("{}{}", truncated, ELLIPSIS)
+}
+
 /// Returns the specified `DateTime<UTC>` as a Discord-compatible ISO 8601
 /// `String`.
 #[inline]
@@ -64,6 +424,62 @@ pub fn timestamp_to_string(timestamp: &DateTime<UTC>) -> String {
     format!("{}", timestamp.format("%Y-%m-%dT%H:%M:%SZ"))
 }
 
+/// Returns the time at which the Discord object with the specified ID (a
+/// "snowflake") was created.
+pub fn snowflake_to_timestamp(id: u64) -> DateTime<UTC> {
+    // The first second of 2015, in milliseconds since the Unix epoch.
+    const DISCORD_EPOCH: u64 = 1420070400000;
+
+    let milliseconds = (id >> 22) + DISCORD_EPOCH;
+    #[cfg_attr(feature = "clippy", allow(cast_possible_truncation, cast_possible_wrap))]
+    let (seconds, nanoseconds) = ((milliseconds / 1000) as i64,
+                                  (milliseconds % 1000) as u32 * 1000000);
+    UTC.timestamp(seconds, nanoseconds)
+}
+
+/// Returns the specified `DateTime<UTC>` as a human-readable `String` in the
+/// timezone configured for display, e.g. "2017-01-31 18:00:00 GMT".
+pub fn timestamp_to_string_tz(timestamp: &DateTime<UTC>) -> String {
+    let timezone = match current_config().timezone {
+        Some(ref timezone) => {
+            timezone.parse::<Tz>().unwrap_or_else(|_| {
+                warn!("Invalid timezone \"{}\", using UTC instead", timezone);
+                Tz::UTC
+            })
+        },
+        None => Tz::UTC,
+    };
+
+    format!("{}", timestamp.with_timezone(&timezone).format("%Y-%m-%d %H:%M:%S %Z"))
+}
+
+/// Returns how long ago the specified `DateTime<UTC>` was as a human-readable
+/// `String`, e.g. "3 months ago". Timestamps in the future (e.g. due to clock
+/// skew) are treated as "just now".
+pub fn time_ago(timestamp: &DateTime<UTC>) -> String {
+    let elapsed = UTC::now() - *timestamp;
+
+    let (amount, unit) = if elapsed.num_days() >= 365 {
+        (elapsed.num_days() / 365, "year")
+    } else if elapsed.num_days() >= 30 {
+        (elapsed.num_days() / 30, "month")
+    } else if elapsed.num_days() >= 1 {
+        (elapsed.num_days(), "day")
+    } else if elapsed.num_hours() >= 1 {
+        (elapsed.num_hours(), "hour")
+    } else if elapsed.num_minutes() >= 1 {
+        (elapsed.num_minutes(), "minute")
+    } else {
+        return "just now".to_owned();
+    };
+
+    if amount == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", amount, unit)
+    }
+}
+
 /// Returns the specified `Duration` as a `String` in the format "Wd Xh Ym Zs"
 #[inline]
 pub fn duration_to_string(duration: &Duration) -> String {
@@ -99,3 +515,305 @@ pub fn stringify<E>(error: E) -> String
 {
     format!("Error: {:?}", error)
 }
+
+/// Shows the typing indicator in the channel while `f` produces a response,
+/// returning its result.
+///
+/// The indicator is purely cosmetic, so failing to show it is logged rather
+/// than returned.
+pub fn with_typing<F, T>(context: &Context, channel_id: ChannelId, f: F) -> T
+    where F: FnOnce() -> T,
+{
+    if let Err(err) = context.broadcast_typing(channel_id) {
+        warn!("Failed to broadcast typing in {}: {:?}", channel_id, err);
+    }
+
+    f()
+}
+
+/// Returns the resident set size of the current process in bytes, or `None`
+/// if it cannot be determined.
+#[cfg(target_os = "linux")]
+pub fn resident_memory() -> Option<u64> {
+    use std::io::Read;
+
+    let mut status = String::new();
+    if File::open("/proc/self/status").and_then(|mut file| file.read_to_string(&mut status))
+        .is_err() {
+        return None;
+    }
+
+    // The line is of the form "VmRSS:     1234 kB".
+    status.lines()
+        .find(|line| line.starts_with("VmRSS:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|kilobytes| kilobytes.parse::<u64>().ok())
+        .map(|kilobytes| kilobytes * 1024)
+}
+
+/// Returns the resident set size of the current process in bytes, or `None`
+/// if it cannot be determined.
+#[cfg(not(target_os = "linux"))]
+pub fn resident_memory() -> Option<u64> {
+    None
+}
+
+/// Returns the path of `name` within the configured data directory, in which
+/// all persisted state is stored, creating the directory if it is missing.
+/// Absolute paths are returned unchanged.
+pub fn data_path(name: &str) -> String {
+    let dir = current_config().data_dir.clone();
+    if let Err(err) = fs::create_dir_all(&dir) {
+        warn!("Failed to create data directory \"{}\": {}", dir, err);
+    }
+
+    Path::new(&dir).join(name).to_string_lossy().into_owned()
+}
+
+/// Writes `contents` to the file at `path` by first writing them to a
+/// uniquely-named temporary file in the same directory, then renaming it over
+/// the target. The directory is created if it is missing.
+///
+/// This ensures that the file at `path` is never left partially written, and
+/// that the rename never crosses filesystems. If writing the temporary file
+/// fails, it is removed.
+pub fn write_atomically<P: AsRef<Path>>(path: P, contents: &[u8]) -> io::Result<()> {
+    let path = path.as_ref();
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name.to_string_lossy().into_owned(),
+        None => {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      format!("Not a file path: {}", path.display())))
+        },
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp = path.with_file_name(format!(".{}-{}.tmp", file_name, Uuid::new_v4()));
+
+    let result = File::create(&temp)
+        .and_then(|mut file| file.write_all(contents).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+
+    result
+}
+
+/// A value, e.g. a map of settings keyed by guild, which is persisted to a
+/// single JSON file. Every modification is saved immediately.
+#[derive(Debug)]
+pub struct JsonStore<T> {
+    name: String,
+    value: Mutex<T>,
+}
+
+impl<T: Deserialize + Serialize + Default> JsonStore<T> {
+    /// Loads the value persisted at `name`. A missing or corrupt file should
+    /// not take down the bot, so the default value is used instead.
+    pub fn load(name: &str) -> Self {
+        let value = match JsonStore::<T>::read_file(name) {
+            Ok(value) => {
+                debug!("Loaded: {}", name);
+                value
+            },
+            Err(err) => {
+                warn!("Failed to load \"{}\": {}", name, err);
+                T::default()
+            },
+        };
+
+        JsonStore {
+            name: name.to_owned(),
+            value: Mutex::new(value),
+        }
+    }
+
+    fn read_file(name: &str) -> SmexybotResult<T> {
+        let mut file = File::open(name)?;
+        let mut value = String::new();
+        file.read_to_string(&mut value)?;
+
+        Ok(serde_json::from_str(&value)?)
+    }
+
+    /// Persists the value.
+    pub fn save(&self, value: &T) -> SmexybotResult<()> {
+        write_atomically(&self.name, serde_json::to_string(value)?.as_bytes())?;
+        trace!("Saved: {}", self.name);
+
+        Ok(())
+    }
+
+    /// Runs `f` against the value while holding the lock.
+    pub fn read<F, R>(&self, f: F) -> R
+        where F: FnOnce(&T) -> R,
+    {
+        let value = self.value.lock().expect("Failed to lock store");
+        f(&*value)
+    }
+
+    /// Runs `f` against the value while holding the lock, so that concurrent
+    /// modifications never overwrite one another, then persists the change,
+    /// unless `f` fails.
+    pub fn modify<F, R, E>(&self, f: F) -> StdResult<R, E>
+        where F: FnOnce(&mut T) -> StdResult<R, E>,
+              E: From<SmexybotError>,
+    {
+        let mut value = self.value.lock().expect("Failed to lock store");
+        let result = f(&mut *value)?;
+        self.save(&*value)?;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use super::*;
+
+    #[test]
+    fn write_atomically_into_new_directory() {
+        let root = env::temp_dir().join(Uuid::new_v4().to_string());
+        let dir = root.join("data");
+        let path = dir.join("tags.json");
+        write_atomically(&path, b"{}").expect("Failed to write file");
+
+        let mut contents = String::new();
+        File::open(&path).and_then(|mut file| file.read_to_string(&mut contents)).unwrap();
+        assert_eq!(contents, "{}");
+        let names = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect::<Vec<String>>();
+        assert_eq!(names, vec!["tags.json".to_owned()]);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn retry_until_success() {
+        let mut calls = 0;
+        let result = {
+            let f = || {
+                calls += 1;
+                if calls < 3 {
+                    Err("Connection reset".to_owned())
+                } else {
+                    Ok(calls)
+                }
+            };
+            retry(f, |_| true, 3, 0)
+        };
+        assert_eq!(result, Ok(3));
+    }
+
+    #[test]
+    fn retry_returns_permanent_error() {
+        let mut calls = 0;
+        let result: StdResult<(), String> = {
+            let f = || {
+                calls += 1;
+                Err("Not found".to_owned())
+            };
+            retry(f, |_| false, 3, 0)
+        };
+        assert_eq!(result, Err("Not found".to_owned()));
+        assert_eq!(calls, 1);
+    }
+
+    // Serves one request with each of the statuses in turn, e.g. "200 OK",
+    // from a local server. Returns the server's URL and the number of requests
+    // it has received.
+    fn serve(statuses: Vec<&'static str>) -> (Url, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind server");
+        let url = format!("http://{}/", listener.local_addr().unwrap()).parse::<Url>().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let received = requests.clone();
+        thread::spawn(move || {
+            for (stream, status) in listener.incoming().zip(statuses) {
+                let mut stream = stream.expect("Failed to accept connection");
+                received.fetch_add(1, Ordering::SeqCst);
+
+                // Read the whole request before responding.
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+
+                let _ = write!(stream,
+                               "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                               status);
+            }
+        });
+
+        (url, requests)
+    }
+
+    #[test]
+    fn get_with_retry_retries_server_errors() {
+        let (url, requests) = serve(vec!["500 Internal Server Error", "503 Service Unavailable",
+                                         "200 OK"]);
+        let response = get_with_retry(&Client::new(), &url, 3, 0).expect("Request failed");
+        assert_eq!(response.status, StatusCode::Ok);
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn get_with_retry_returns_client_errors() {
+        let (url, requests) = serve(vec!["404 Not Found", "200 OK"]);
+        let response = get_with_retry(&Client::new(), &url, 3, 0).expect("Request failed");
+        assert_eq!(response.status, StatusCode::NotFound);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn get_with_retry_gives_up() {
+        let (url, requests) = serve(vec!["500 Internal Server Error"; 3]);
+        match get_with_retry(&Client::new(), &url, 2, 0) {
+            Err(SmexybotError::Status(StatusCode::InternalServerError)) => {},
+            result => panic!("Unexpected result: {:?}", result.map(|response| response.status)),
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    // Returns the red, green and blue components of the colour.
+    fn components(colour: Colour) -> (u8, u8, u8) {
+        (colour.get_r(), colour.get_g(), colour.get_b())
+    }
+
+    #[test]
+    fn readable_colour_extremes() {
+        assert_eq!(components(readable_colour(0.0, 0.0, 0.0)), (153, 77, 77));
+        assert_eq!(components(readable_colour(1.0, 1.0, 1.0)), (242, 24, 24));
+    }
+
+    #[test]
+    fn random_colour_is_readable() {
+        // With the value between 0.6 and 0.95, the brightest component is
+        // between 153 and 242; with the saturation between 0.5 and 0.9 as
+        // well, the darkest is between 15 and 121.
+        for _ in 0..1000 {
+            let (red, green, blue) = components(random_colour());
+            let brightest = cmp::max(red, cmp::max(green, blue));
+            let darkest = cmp::min(red, cmp::min(green, blue));
+            assert!(brightest >= 153 && brightest <= 242);
+            assert!(darkest >= 15 && darkest <= 121);
+        }
+    }
+
+    #[test]
+    fn colour_from_hash_is_stable() {
+        assert_eq!(components(colour_from_hash("rules")), components(colour_from_hash("rules")));
+        assert!(components(colour_from_hash("rules")) != components(colour_from_hash("faq")));
+    }
+}
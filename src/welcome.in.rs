@@ -0,0 +1,9 @@
+/// The message with which a guild welcomes its new members.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Welcome {
+    /// The ID of the channel the message is posted in.
+    pub channel_id: u64,
+    /// The message, in which `%user%` is replaced with a mention of the new
+    /// member and `%guild%` with the name of the guild.
+    pub message: String,
+}
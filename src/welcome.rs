@@ -0,0 +1,62 @@
+// Copyright (c) 2016 Nikita Pekin and the smexybot contributors
+// See the README.md file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Stores the messages with which guilds welcome their new members.
+
+use error::Result;
+use serenity::model::GuildId;
+use std::collections::HashMap;
+use util::{JsonStore, data_path};
+
+#[cfg(feature = "nightly")]
+include!("welcome.in.rs");
+
+#[cfg(feature = "with-syntex")]
+include!(concat!(env!("OUT_DIR"), "/welcome.rs"));
+
+const WELCOMES_FILE: &'static str = "welcomes.json";
+
+lazy_static! {
+    pub static ref WELCOMES: Welcomes = Welcomes::new(&data_path(WELCOMES_FILE));
+}
+
+impl Welcome {
+    /// Returns the message to post for the new member of the guild.
+    pub fn render(&self, user_mention: &str, guild_name: &str) -> String {
+        self.message.replace("%user%", user_mention).replace("%guild%", guild_name)
+    }
+}
+
+#[derive(Debug)]
+pub struct Welcomes {
+    welcomes: JsonStore<HashMap<String, Welcome>>,
+}
+
+impl Welcomes {
+    fn new(name: &str) -> Self {
+        Welcomes { welcomes: JsonStore::load(name) }
+    }
+
+    /// Returns the welcome message configured for the guild, if any.
+    pub fn get(&self, guild_id: GuildId) -> Option<Welcome> {
+        self.welcomes.read(|welcomes| welcomes.get(&guild_id.to_string()).cloned())
+    }
+
+    /// Sets the welcome message for the guild, or disables welcoming new
+    /// members if `None` is given, then persists the change.
+    pub fn set(&self, guild_id: GuildId, welcome: Option<Welcome>) -> Result<()> {
+        self.welcomes.modify(|welcomes| {
+            match welcome {
+                Some(welcome) => welcomes.insert(guild_id.to_string(), welcome),
+                None => welcomes.remove(&guild_id.to_string()),
+            };
+            Ok(())
+        })
+    }
+}